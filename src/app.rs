@@ -1,32 +1,434 @@
+use crate::{
+    config::{self, CONFIG_FILE},
+    keymap::KeyMap,
+    model::{
+        BOOKMARKS_FILE, Baseline, Bookmarks, DiffEnv, DiffStatus, EnvList, EnvSource, Environment,
+        FileEnv, KeyFilter, ProcessEnv, Search, Shell, SortMode, eval_lines, to_json,
+        utc_timestamp, write_dotenv, write_export, write_json, write_snapshot,
+    },
+    state::UiState,
+    theme::Theme,
+};
+use arboard::Clipboard;
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{self, KeyCode, KeyEvent};
 use ratatui::{
-    DefaultTerminal,
-    buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
-    style::{
-        Modifier, Style, Stylize,
-        palette::tailwind::{BLUE, SLATE},
-    },
-    symbols,
-    text::Line,
-    widgets::{
-        Block, Borders, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph,
-        StatefulWidget, Widget, Wrap,
-    },
+    Terminal,
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{HighlightSpacing, List, Table},
 };
-use std::{env, fmt::Display};
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+mod events;
+mod ui;
+
+use ui::decode_base64;
+
+/// where `.env` exports are written unless `ENVIRUST_EXPORT` is set; JSON
+/// exports use the same path with a `.json` extension, and exports of a
+/// single variable the extension of the shell.
+const DEFAULT_EXPORT_PATH: &str = "./envirust.env";
+
+/// how many lines the value pane moves per mouse wheel step.
+const WHEEL_STEP: u16 = 3;
+
+/// how many columns the value pane moves per horizontal scroll.
+const HSCROLL_STEP: u16 = 8;
+
+/// key patterns of variables masked unless the config replaces them, `*`
+/// matches any run of chars.
+const SECRET_PATTERNS: &[&str] = &[
+    "*SECRET*",
+    "*TOKEN*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*API_KEY*",
+    "*PRIVATE_KEY*",
+    "*CREDENTIAL*",
+];
+
+/// what the values of secrets are exported as unless asked to include them.
+const REDACTED: &str = "<redacted>";
+
+/// the bounds of the share of the main area given to the list, in percent.
+const MIN_SPLIT: u16 = 10;
+const MAX_SPLIT: u16 = 90;
+
+/// how many percent `+` and `-` move the split between the panes.
+const SPLIT_STEP: i16 = 10;
 
+/// how often the event loop wakes up without input to update time-based state.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// how long a message stays on screen without a keypress.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// the terminal UI for browsing the variables.
 pub struct App {
     is_running: bool,
+    /// whether to start a shell with the variables once the UI is closed.
+    relaunch: bool,
+    /// the variables as loaded, kept with `--eval` to print the changes on quit.
+    original: Option<Vec<Environment>>,
     env_list: EnvList,
+    search: Search,
+    /// feedback from the last action, cleared on the next keypress or after
+    /// `MESSAGE_TIMEOUT`.
+    message: Option<Message>,
+    /// when the current message was first seen by a tick.
+    message_shown: Option<Instant>,
+    /// whether the variables are read again on every tick.
+    watch: bool,
+    /// kept alive so the copied contents stay available on X11.
+    clipboard: Option<Clipboard>,
+    export_path: PathBuf,
+    /// the syntax a single exported variable is written in.
+    shell: Shell,
+    /// whether exports write the values of secrets instead of redacting them.
+    include_secrets: bool,
+    masking: Masking,
+    highlight: Highlight,
+    descriptions: Descriptions,
+    bookmarks: Bookmarks,
+    /// whether list items are prefixed with their position.
+    line_numbers: bool,
+    /// whether long values wrap in the value pane.
+    wrap: bool,
+    /// whether JSON values are pretty-printed.
+    pretty_json: bool,
+    /// whether the selected value is shown base64-decoded.
+    decoded: bool,
+    /// whether parts of the value that match other values are underlined.
+    cross_references: bool,
+    /// whether the list shows the values in place of the keys.
+    list_values: bool,
+    /// whether the value pane lists the variables containing the value.
+    show_referrers: bool,
+    /// the variables containing the shown value, kept until another
+    /// variable or value is shown.
+    referrers: Option<Referrers>,
+    /// whether `$VAR` references in the value are resolved below it.
+    expand: bool,
+    /// whether control chars in the value reach the terminal unescaped.
+    raw: bool,
+    /// whether paths in the value are looked up on disk.
+    stat_paths: bool,
+    /// whether path lists leave out the paths already listed.
+    unique_paths: bool,
+    keymap: KeyMap,
+    theme: Theme,
+    /// whether colors are used, off with `NO_COLOR` or `--no-color`.
+    color: bool,
+    /// the built-in theme picked with `C`, saved over the configured one.
+    theme_name: Option<String>,
+    /// whether the UI state came from `state.toml` and is saved back on quit.
+    persist: bool,
+    /// set on resize so the next frame repaints the whole screen.
+    resized: bool,
+    /// whether the screen is out of date, so the next loop draws a frame.
+    redraw: bool,
+    /// where the list was drawn in the last render.
+    list_area: Rect,
+    /// whether the list is drawn as a table of keys and values.
+    columns: bool,
+    /// the share of the main area given to the list, in percent.
+    split: u16,
+    /// whether the value pane is shown below the list.
+    value_pane: bool,
+    /// the key of the variable the value pane stays on while the selection
+    /// moves.
+    pinned: Option<String>,
+    /// the list drawn in the last render, rebuilt when the view is dirty.
+    list: List<'static>,
+    /// the table drawn instead of `list` in the last render, in column mode.
+    table: Table<'static>,
+    /// the rows of the view that `list` or `table` holds.
+    list_rows: Range<usize>,
+    /// the width the rows in `list` or `table` were cut to.
+    list_width: usize,
+    /// where the value pane was drawn in the last render.
+    value_area: Rect,
+    /// the pane driven by the movement keys.
+    focus: Focus,
+    /// the count typed before a movement key, like the 5 in `5j`.
+    count: Option<usize>,
+    /// the first key of a two-key command like `yy`, waiting for the second.
+    operator: Option<Operator>,
+    /// the variable yanked or deleted last, put back with `p`.
+    register: Option<Environment>,
+    /// the keys of the variables picked for bulk actions, one at a time or
+    /// by a regex.
+    selection: HashSet<String>,
+    /// a dialog drawn over the UI that captures all keys.
+    overlay: Option<Overlay>,
+    /// the text input shown in the footer, if any.
+    prompt: Option<Prompt>,
 }
 
 impl App {
+    /// Create an app showing the variables of this process.
     pub fn new() -> Self {
-        Self {
+        let env_list = EnvList::new(Box::new(ProcessEnv)).expect("the process environment loads");
+        Self::with_env_list(env_list)
+    }
+
+    /// Create an app showing the variables of the `.env` file at `path`.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let env_list = EnvList::new(Box::new(FileEnv::new(path)))?;
+        Ok(Self::with_env_list(env_list))
+    }
+
+    /// Create an app showing how the live environment differs from the
+    /// `.env` file at `path`.
+    pub fn diff_file(path: &Path) -> io::Result<Self> {
+        let env_list = EnvList::new(Box::new(DiffEnv::new(path)))?;
+        Ok(Self::with_env_list(env_list))
+    }
+
+    /// Create an app showing how the live environment differs from the
+    /// snapshot at `path`, written earlier with `W`.
+    pub fn compare_snapshot(path: &Path) -> io::Result<Self> {
+        let env_list = EnvList::new(Box::new(DiffEnv::snapshot(path)))?;
+        Ok(Self::with_env_list(env_list))
+    }
+
+    /// Mark the variables by how they differ from the `.env` file at `path`.
+    pub fn with_baseline(mut self, path: &Path) -> io::Result<Self> {
+        let (items, _) = FileEnv::new(path).load()?;
+        let baseline = Baseline {
+            origin: path.display().to_string(),
+            items,
+        };
+        self.env_list
+            .set_baseline(Some(baseline), &self.search, &self.bookmarks);
+        Ok(self)
+    }
+
+    /// Start with the list filtered by the search `query`.
+    pub fn with_filter(mut self, query: &str) -> Self {
+        self.search.query = query.to_string();
+        self.refilter();
+        self
+    }
+
+    /// Start with the list narrowed to keys starting with `prefix`.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.set_prefix(prefix.to_string());
+        self
+    }
+
+    /// Start with the list sorted by key.
+    pub fn sorted(mut self) -> Self {
+        self.env_list.sort = SortMode::KeyAsc;
+        self.env_list.resort();
+        self
+    }
+
+    /// Export single variables in the syntax of `shell`.
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Write the real values of secrets in exports without asking.
+    pub fn including_secrets(mut self) -> Self {
+        self.include_secrets = true;
+        self
+    }
+
+    /// Print the changes made in the UI as shell statements on quit, see
+    /// [`App::write_eval`].
+    pub fn evaluating(mut self) -> Self {
+        self.original = Some(self.env_list.snapshot());
+        self
+    }
+
+    /// Draw with the default colors of the terminal only, using bold and
+    /// reversed text for emphasis.
+    pub fn without_color(mut self) -> Self {
+        self.color = false;
+        self.theme = Theme::monochrome();
+        self.env_list.dirty = true;
+        self
+    }
+
+    /// Read the variables again on every tick, flashing the changed ones.
+    pub fn watching(mut self) -> Self {
+        self.watch = true;
+        self
+    }
+
+    /// The shown variables as a JSON object, with secrets redacted unless
+    /// they are included.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        to_json(&self.listed())
+    }
+
+    /// Write the shown variables to `out` as `KEY=VALUE` lines, with secrets
+    /// redacted unless they are included.
+    pub fn write_list(&self, out: &mut impl Write) -> io::Result<()> {
+        for item in self.listed() {
+            writeln!(out, "{item}")?;
+        }
+        Ok(())
+    }
+
+    /// The shown variables for `--json` and `--list`, redacted by the same
+    /// rule as exports from the TUI.
+    fn listed(&self) -> Vec<Environment> {
+        let shown: Vec<Environment> = self.env_list.shown().cloned().collect();
+        self.exported(&shown, self.include_secrets)
+    }
+
+    /// How many of the variables printed by [`App::to_json`] and
+    /// [`App::write_list`] have their values redacted.
+    pub fn redacted_count(&self) -> usize {
+        if self.include_secrets {
+            return 0;
+        }
+        self.env_list
+            .shown()
+            .filter(|item| self.is_redacted(item))
+            .count()
+    }
+
+    /// Create an app with the settings of the config directory and the
+    /// default UI state, which [`App::restoring_state`] replaces.
+    fn with_env_list(env_list: EnvList) -> Self {
+        let app = Self::with_settings(env_list, Settings::load(), UiState::default());
+        // https://no-color.org asks to ignore an empty value.
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            app.without_color()
+        } else {
+            app
+        }
+    }
+
+    fn with_settings(env_list: EnvList, settings: Settings, state: UiState) -> Self {
+        let Settings {
+            keymap,
+            theme,
+            masking,
+            highlight,
+            descriptions,
+            bookmarks,
+            warnings,
+        } = settings;
+        let mut app = Self {
             is_running: true,
-            env_list: EnvList::new(),
+            relaunch: false,
+            original: None,
+            env_list,
+            search: Search::default(),
+            message: (!warnings.is_empty()).then(|| Message::Error(warnings.join("; "))),
+            message_shown: None,
+            watch: false,
+            clipboard: None,
+            export_path: env::var_os("ENVIRUST_EXPORT")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_EXPORT_PATH)),
+            shell: Shell::default(),
+            include_secrets: false,
+            highlight,
+            descriptions,
+            masking,
+            bookmarks,
+            line_numbers: false,
+            wrap: true,
+            pretty_json: true,
+            decoded: false,
+            cross_references: false,
+            list_values: false,
+            show_referrers: false,
+            referrers: None,
+            expand: false,
+            raw: false,
+            stat_paths: false,
+            unique_paths: false,
+            keymap,
+            theme,
+            color: true,
+            theme_name: None,
+            persist: false,
+            resized: false,
+            redraw: true,
+            list_area: Rect::default(),
+            columns: false,
+            split: 50,
+            value_pane: true,
+            pinned: None,
+            list: List::default(),
+            table: Table::default(),
+            list_rows: 0..0,
+            list_width: 0,
+            value_area: Rect::default(),
+            focus: Focus::default(),
+            count: None,
+            operator: None,
+            register: None,
+            selection: HashSet::new(),
+            overlay: None,
+            prompt: None,
+        };
+        app.apply_state(state);
+        app
+    }
+
+    /// Restore the sort, masking and layout saved by the last session, and
+    /// save them again on quit.
+    ///
+    /// Only for the TUI: `--list` and `--json` keep the defaults, so their
+    /// output never depends on what the last session left behind.
+    pub fn restoring_state(mut self) -> Self {
+        self.apply_state(UiState::load());
+        self.persist = true;
+        self
+    }
+
+    fn apply_state(&mut self, state: UiState) {
+        self.masking.enabled = state.masking;
+        self.mask_search();
+        self.line_numbers = state.line_numbers;
+        self.wrap = state.wrap;
+        self.columns = state.columns;
+        self.split = state.split.clamp(MIN_SPLIT, MAX_SPLIT);
+        self.value_pane = state.value_pane;
+        // without colors, the theme stays monochrome.
+        if self.color
+            && let Some(theme) = state.theme.as_deref().and_then(Theme::builtin)
+        {
+            self.theme = theme;
+            self.theme_name = state.theme;
+        }
+        self.env_list.sort_by(state.sort, state.direction);
+    }
+
+    /// The preferences to restore in the next session.
+    fn ui_state(&self) -> UiState {
+        UiState {
+            sort: self.env_list.sort,
+            direction: self.env_list.direction,
+            masking: self.masking.enabled,
+            wrap: self.wrap,
+            line_numbers: self.line_numbers,
+            columns: self.columns,
+            split: self.split,
+            value_pane: self.value_pane,
+            theme: self.theme_name.clone(),
+            ..UiState::default()
         }
     }
 }
@@ -37,167 +439,1210 @@ impl Default for App {
     }
 }
 
-/// the environment list.
-pub struct EnvList {
-    items: Vec<Environment>,
-    state: ListState,
+/// the keys of the variables whose values contain the value of `key`.
+struct Referrers {
+    key: String,
+    /// the value searched for, so an edit searches again.
+    value: String,
+    keys: Vec<String>,
+}
+
+/// what the config directory sets, with the bookmarks from the state
+/// directory and warnings about anything that could not be used.
+#[derive(Default)]
+struct Settings {
+    keymap: KeyMap,
+    theme: Theme,
+    masking: Masking,
+    highlight: Highlight,
+    descriptions: Descriptions,
+    bookmarks: Bookmarks,
+    warnings: Vec<String>,
 }
 
-impl EnvList {
-    fn new() -> Self {
+impl Settings {
+    fn load() -> Self {
+        let (keymap, mut warnings) = KeyMap::load();
+        let (theme, theme_warnings) = Theme::load();
+        warnings.extend(theme_warnings);
+        let (masking, masking_warnings) = Masking::load();
+        warnings.extend(masking_warnings);
+        let (highlight, highlight_warnings) = Highlight::load();
+        warnings.extend(highlight_warnings);
+        let (descriptions, description_warnings) = Descriptions::load();
+        warnings.extend(description_warnings);
+        let bookmarks = Bookmarks::load().unwrap_or_else(|err| {
+            warnings.push(format!("could not read {BOOKMARKS_FILE}: {err}"));
+            Bookmarks::default()
+        });
         Self {
-            items: get_variables(),
-            state: ListState::default(),
+            keymap,
+            theme,
+            masking,
+            highlight,
+            descriptions,
+            bookmarks,
+            warnings,
         }
     }
 }
 
-/// Enviroment struct, containing the key and value.
-#[derive(Debug)]
-struct Environment {
-    key: String,
-    value: String,
+/// how the selected list item is marked.
+struct Highlight {
+    /// drawn before the selected item.
+    symbol: String,
+    /// when the column of the symbol is kept.
+    spacing: HighlightSpacing,
 }
 
-impl Environment {
-    /// Create a new struct from key, and string.
-    fn new(key: String, value: String) -> Self {
-        Self { key, value }
+impl Default for Highlight {
+    fn default() -> Self {
+        Self {
+            symbol: ">".to_string(),
+            spacing: HighlightSpacing::Always,
+        }
+    }
+}
+
+impl Highlight {
+    /// Load the highlight from the `[list]` table of `config.toml`, which may
+    /// set `highlight_symbol` and `highlight_spacing`.
+    fn load() -> (Self, Vec<String>) {
+        match config::read_config(CONFIG_FILE) {
+            Ok(Some(contents)) => Self::from_toml(&contents),
+            _ => (Self::default(), Vec::new()),
+        }
+    }
+
+    fn from_toml(contents: &str) -> (Self, Vec<String>) {
+        let mut highlight = Self::default();
+        let mut warnings = Vec::new();
+        let Ok(table) = toml::from_str::<toml::Table>(contents) else {
+            return (highlight, warnings);
+        };
+        let Some(table) = table.get("list") else {
+            return (highlight, warnings);
+        };
+        let Some(table) = table.as_table() else {
+            warnings.push("list must be a table".to_string());
+            return (highlight, warnings);
+        };
+
+        for (name, value) in table {
+            match (name.as_str(), value.as_str()) {
+                ("highlight_symbol", Some(symbol)) => {
+                    highlight.symbol = symbol.to_string();
+                }
+                ("highlight_spacing", Some("always")) => {
+                    highlight.spacing = HighlightSpacing::Always;
+                }
+                ("highlight_spacing", Some("when_selected")) => {
+                    highlight.spacing = HighlightSpacing::WhenSelected;
+                }
+                ("highlight_spacing", Some("never")) => highlight.spacing = HighlightSpacing::Never,
+                ("highlight_symbol", None) => {
+                    warnings.push("list.highlight_symbol must be a string".to_string());
+                }
+                ("highlight_spacing", _) => warnings.push(
+                    "list.highlight_spacing must be always, when_selected or never".to_string(),
+                ),
+                _ => warnings.push(format!("unknown list setting '{name}'")),
+            }
+        }
+        (highlight, warnings)
+    }
+
+    /// The columns the symbol takes in front of the items, which depends on
+    /// whether one is `selected` unless the spacing is fixed.
+    fn width(&self, selected: bool) -> usize {
+        match self.spacing {
+            HighlightSpacing::Always => self.symbol.width(),
+            HighlightSpacing::WhenSelected if selected => self.symbol.width(),
+            HighlightSpacing::WhenSelected | HighlightSpacing::Never => 0,
+        }
     }
 }
 
-impl Display for Environment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}={}", self.key, self.value)
+/// what well-known variables are for, shown under the title of the value pane.
+const DESCRIPTIONS: &[(&str, &str)] = &[
+    ("PATH", "directories searched for commands, in order"),
+    ("HOME", "the home directory of the user"),
+    ("USER", "the name of the logged in user"),
+    ("LOGNAME", "the name of the logged in user"),
+    ("SHELL", "the login shell of the user"),
+    ("SHLVL", "how many shells deep this one is nested"),
+    ("PWD", "the current working directory"),
+    ("OLDPWD", "the previous working directory, used by cd -"),
+    (
+        "TERM",
+        "the type of the terminal, telling programs what it supports",
+    ),
+    (
+        "COLORTERM",
+        "set to truecolor or 24bit when the terminal has full color",
+    ),
+    (
+        "NO_COLOR",
+        "asks programs not to use colors when set and not empty",
+    ),
+    ("LANG", "the default language and encoding"),
+    ("LC_ALL", "overrides every LC_ locale setting and LANG"),
+    ("LANGUAGE", "the preferred languages for messages, in order"),
+    ("TZ", "the time zone"),
+    ("EDITOR", "the editor programs start for editing text"),
+    ("VISUAL", "the full-screen editor, preferred over EDITOR"),
+    ("PAGER", "the program used to page through long output"),
+    ("MANPATH", "directories searched for man pages"),
+    ("TMPDIR", "the directory for temporary files"),
+    ("HISTFILE", "the file the shell keeps its history in"),
+    ("LS_COLORS", "the colors ls uses for each kind of file"),
+    (
+        "LD_LIBRARY_PATH",
+        "directories searched for shared libraries first",
+    ),
+    ("DISPLAY", "the X server graphical programs connect to"),
+    (
+        "WAYLAND_DISPLAY",
+        "the Wayland compositor graphical programs connect to",
+    ),
+    (
+        "XDG_SESSION_TYPE",
+        "the kind of graphical session, like x11, wayland or tty",
+    ),
+    (
+        "XDG_CURRENT_DESKTOP",
+        "the desktop environments of the session",
+    ),
+    (
+        "XDG_CONFIG_HOME",
+        "where programs keep their config, ~/.config if unset",
+    ),
+    (
+        "XDG_DATA_HOME",
+        "where programs keep their data, ~/.local/share if unset",
+    ),
+    (
+        "XDG_STATE_HOME",
+        "where programs keep their state, ~/.local/state if unset",
+    ),
+    (
+        "XDG_CACHE_HOME",
+        "where programs keep their caches, ~/.cache if unset",
+    ),
+    (
+        "XDG_RUNTIME_DIR",
+        "the directory for sockets and other files of this session",
+    ),
+    (
+        "DBUS_SESSION_BUS_ADDRESS",
+        "the address of the D-Bus session bus",
+    ),
+    ("SSH_AUTH_SOCK", "the socket of the SSH agent"),
+    (
+        "SSH_CONNECTION",
+        "the addresses and ports of the SSH connection",
+    ),
+    ("http_proxy", "the proxy for HTTP requests"),
+    ("https_proxy", "the proxy for HTTPS requests"),
+    ("no_proxy", "hosts reached without the proxy"),
+    (
+        "CARGO_HOME",
+        "where cargo keeps its registry and installed binaries",
+    ),
+    ("RUSTUP_HOME", "where rustup keeps its toolchains"),
+    (
+        "RUST_LOG",
+        "the log filter of Rust programs using env_logger or tracing",
+    ),
+    (
+        "RUST_BACKTRACE",
+        "whether Rust programs print a backtrace on panic",
+    ),
+    ("GOPATH", "the workspace of the Go tools"),
+    ("JAVA_HOME", "the Java installation used by build tools"),
+    ("VIRTUAL_ENV", "the active Python virtual environment"),
+    ("USERPROFILE", "the home directory of the user on Windows"),
+    ("APPDATA", "where Windows programs keep their roaming data"),
+    ("COMSPEC", "the command interpreter on Windows"),
+    ("PATHEXT", "the extensions Windows treats as commands"),
+];
+
+/// one-line descriptions of variables, the built-in ones with those from the
+/// config.
+struct Descriptions {
+    entries: HashMap<String, String>,
+}
+
+impl Default for Descriptions {
+    fn default() -> Self {
+        let entries = DESCRIPTIONS
+            .iter()
+            .map(|&(key, description)| (key.to_string(), description.to_string()))
+            .collect();
+        Self { entries }
     }
 }
 
-fn get_variables() -> Vec<Environment> {
-    let envs = env::vars();
-    let mut variables: Vec<Environment> = Vec::new();
+impl Descriptions {
+    /// Load the descriptions, with the `[descriptions]` table of
+    /// `config.toml` adding to the built-in ones. An empty description hides
+    /// the built-in one.
+    fn load() -> (Self, Vec<String>) {
+        match config::read_config(CONFIG_FILE) {
+            Ok(Some(contents)) => Self::from_toml(&contents),
+            _ => (Self::default(), Vec::new()),
+        }
+    }
+
+    fn from_toml(contents: &str) -> (Self, Vec<String>) {
+        let mut descriptions = Self::default();
+        let mut warnings = Vec::new();
+        let Ok(table) = toml::from_str::<toml::Table>(contents) else {
+            return (descriptions, warnings);
+        };
+        let Some(table) = table.get("descriptions") else {
+            return (descriptions, warnings);
+        };
+        let Some(table) = table.as_table() else {
+            warnings.push("descriptions must be a table".to_string());
+            return (descriptions, warnings);
+        };
 
-    for (key, value) in envs {
-        variables.push(Environment::new(key, value));
+        for (key, value) in table {
+            match value.as_str() {
+                Some("") => {
+                    descriptions.entries.remove(key);
+                }
+                Some(description) => {
+                    descriptions
+                        .entries
+                        .insert(key.clone(), description.to_string());
+                }
+                None => warnings.push(format!("the description of {key} must be a string")),
+            }
+        }
+        (descriptions, warnings)
+    }
+
+    /// The description of the variable `key`, if it has one.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
     }
-    variables
+}
+
+/// hides the values of variables that look like secrets.
+#[derive(Clone)]
+struct Masking {
+    enabled: bool,
+    /// key patterns, matched case-insensitively.
+    patterns: Vec<String>,
+    /// key regexes from the config, built case-insensitive.
+    regexes: Vec<Regex>,
+    /// the key of the one variable shown despite being masked.
+    revealed: Option<String>,
+}
+
+impl Default for Masking {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: SECRET_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            regexes: Vec::new(),
+            revealed: None,
+        }
+    }
+}
+
+impl Masking {
+    /// Load the secret patterns from the `[masking]` table of `config.toml`.
+    ///
+    /// `patterns` adds globs and `regexes` adds regular expressions to the
+    /// defaults, or replaces them with `defaults = false`. Problems with the
+    /// file itself are reported by the theme, which is read from it too.
+    fn load() -> (Self, Vec<String>) {
+        match config::read_config(CONFIG_FILE) {
+            Ok(Some(contents)) => Self::from_toml(&contents),
+            _ => (Self::default(), Vec::new()),
+        }
+    }
+
+    fn from_toml(contents: &str) -> (Self, Vec<String>) {
+        let mut masking = Self::default();
+        let mut warnings = Vec::new();
+        let Ok(table) = toml::from_str::<toml::Table>(contents) else {
+            return (masking, warnings);
+        };
+        let Some(table) = table.get("masking") else {
+            return (masking, warnings);
+        };
+        let Some(table) = table.as_table() else {
+            warnings.push("masking must be a table".to_string());
+            return (masking, warnings);
+        };
+
+        let mut strings = |name: &str| -> Vec<String> {
+            let Some(value) = table.get(name) else {
+                return Vec::new();
+            };
+            let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+            let strings: Option<Vec<String>> = items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect();
+            match strings {
+                Some(strings) if value.is_array() => strings,
+                _ => {
+                    warnings.push(format!("masking.{name} must be a list of strings"));
+                    Vec::new()
+                }
+            }
+        };
+        let patterns = strings("patterns");
+        let regexes = strings("regexes");
+
+        match table.get("defaults").map(|value| value.as_bool()) {
+            None | Some(Some(true)) => {}
+            Some(Some(false)) => masking.patterns.clear(),
+            Some(None) => warnings.push("masking.defaults must be true or false".to_string()),
+        }
+        masking.patterns.extend(patterns);
+        for pattern in regexes {
+            match RegexBuilder::new(&pattern).case_insensitive(true).build() {
+                Ok(regex) => masking.regexes.push(regex),
+                Err(err) => {
+                    let message = err.to_string();
+                    let reason = message.lines().last().unwrap_or_default();
+                    warnings.push(format!("invalid masking regex '{pattern}': {reason}"));
+                }
+            }
+        }
+        for name in table.keys() {
+            if !["patterns", "regexes", "defaults"].contains(&name.as_str()) {
+                warnings.push(format!("unknown masking setting '{name}'"));
+            }
+        }
+        (masking, warnings)
+    }
+
+    /// Whether the value of `item` should be hidden.
+    fn is_masked(&self, item: &Environment) -> bool {
+        self.enabled && self.is_secret(&item.key) && self.revealed.as_ref() != Some(&item.key)
+    }
+
+    /// Toggle revealing `item`, if it is a masked secret.
+    fn toggle_reveal(&mut self, item: &Environment) {
+        if self.revealed.as_ref() == Some(&item.key) {
+            self.revealed = None;
+        } else if self.is_masked(item) {
+            self.revealed = Some(item.key.clone());
+        }
+    }
+
+    /// Whether `key` matches one of the secret patterns or regexes.
+    fn is_secret(&self, key: &str) -> bool {
+        let upper = key.to_uppercase();
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(&pattern.to_uppercase(), &upper))
+            || self.regexes.iter().any(|regex| regex.is_match(key))
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any run of chars.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // no `*` at all, the pattern must match exactly.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// commands typed by pressing their key twice, like in vim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    /// `yy`, or twice any key bound to copy.
+    Yank,
+    /// `dd`.
+    Delete,
+}
+
+impl Operator {
+    /// The key shown in the footer while waiting for the second press.
+    fn key(self) -> char {
+        match self {
+            Self::Yank => 'y',
+            Self::Delete => 'd',
+        }
+    }
+}
+
+/// the panes the movement keys can drive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    #[default]
+    List,
+    Value,
+}
+
+impl Focus {
+    fn toggle(self) -> Self {
+        match self {
+            Self::List => Self::Value,
+            Self::Value => Self::List,
+        }
+    }
+}
+
+/// what a file export writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Export {
+    /// all variables as a `.env` file.
+    Dotenv,
+    /// all variables as a JSON object.
+    Json,
+    /// the selected variable as a line for the shell.
+    Selected,
+    /// all variables as a snapshot to compare against later.
+    Snapshot,
+}
+
+/// dialogs drawn over the UI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Overlay {
+    /// asks whether to quit and lose the pending edits.
+    ConfirmQuit,
+    /// asks whether the `secrets` among the exported variables are written
+    /// in clear text.
+    ConfirmExport { export: Export, secrets: usize },
+    /// asks whether to delete the variables `keys`.
+    ConfirmDelete { keys: Vec<String> },
+    /// lists the key bindings, scrolled down by `scroll` lines.
+    Help { scroll: u16 },
+    /// counts the variables by kind and key prefix.
+    Summary,
+}
+
+/// a text input shown in the footer.
+struct Prompt {
+    kind: PromptKind,
+    input: Input,
+}
+
+/// what the prompt input is used for.
+enum PromptKind {
+    /// a new value for the variable `key`.
+    EditValue { key: String },
+    /// the key of a variable to add.
+    NewKey,
+    /// the value of the variable `key` being added.
+    NewValue { key: String },
+    /// the prefix keys must start with.
+    Prefix,
+    /// a regex picking the shown variables to select by key.
+    SelectMatching,
+}
+
+impl PromptKind {
+    fn label(&self) -> String {
+        match self {
+            Self::EditValue { key } | Self::NewValue { key } => format!("{key}="),
+            Self::NewKey => "New variable: ".to_string(),
+            Self::Prefix => "Prefix: ".to_string(),
+            Self::SelectMatching => "Select keys matching: ".to_string(),
+        }
+    }
+}
+
+/// a single line of editable text.
+#[derive(Debug, Default)]
+struct Input {
+    text: String,
+    /// the cursor position, in chars.
+    cursor: usize,
+}
+
+impl Input {
+    /// Create an input holding `text`, with the cursor at the end.
+    fn new(text: String) -> Self {
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    fn byte_index(&self) -> usize {
+        self.text
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn insert(&mut self, c: char) {
+        let index = self.byte_index();
+        self.text.insert(index, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index();
+            self.text.remove(index);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let index = self.byte_index();
+            self.text.remove(index);
+        }
+    }
+
+    fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    fn end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Handle an editing key.
+    fn on_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.left(),
+            KeyCode::Right => self.right(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+            _ => {}
+        }
+    }
+
+    /// Render the text scrolled so the cursor fits in `width` columns.
+    fn line(&self, width: usize, cursor_style: Style) -> Line<'static> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let char_width = |c: &char| c.width().unwrap_or(0);
+        let mut used = chars.get(self.cursor).map_or(1, char_width);
+        let mut start = self.cursor;
+        while start > 0 && used + char_width(&chars[start - 1]) <= width {
+            start -= 1;
+            used += char_width(&chars[start]);
+        }
+        let mut chars = chars.into_iter().skip(start);
+        let before: String = chars.by_ref().take(self.cursor - start).collect();
+        let at = chars.next().map_or(" ".to_string(), String::from);
+        let after: String = chars.collect();
+        Line::from(vec![
+            Span::raw(before),
+            Span::styled(at, cursor_style),
+            Span::raw(after),
+        ])
+    }
+}
+
+/// transient feedback shown in the footer.
+enum Message {
+    Info(String),
+    Error(String),
 }
 
 impl App {
-    pub fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+    /// Draw the UI and handle events until the user quits.
+    pub fn run<B: Backend>(&mut self, mut terminal: Terminal<B>) -> Result<()> {
+        let mut last_tick = Instant::now();
         while self.is_running {
-            terminal.draw(|frame| frame.render_widget(&mut *self, frame.area()))?;
-            self.handle_crossterm_events()?;
+            if std::mem::take(&mut self.resized) {
+                // some terminals leave stale cells behind after a resize, so
+                // drop the previous frame instead of diffing against it.
+                terminal.autoresize()?;
+                terminal.clear()?;
+            }
+            if std::mem::take(&mut self.redraw) {
+                terminal.draw(|frame| frame.render_widget(&mut *self, frame.area()))?;
+            }
+            // wait for input only until the next tick is due.
+            if event::poll(TICK_RATE.saturating_sub(last_tick.elapsed()))? {
+                self.handle_crossterm_events()?;
+            }
+            if last_tick.elapsed() >= TICK_RATE {
+                self.on_tick();
+                last_tick = Instant::now();
+            }
+        }
+        // the preferences are a convenience, failing to save them should not
+        // turn a clean quit into an error.
+        if self.persist {
+            let _ = self.ui_state().save();
         }
         Ok(())
     }
 
-    fn handle_crossterm_events(&mut self) -> Result<()> {
-        match event::read()? {
-            Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
-            Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
-            _ => {}
+    /// Update the time-based state, called every `TICK_RATE`.
+    ///
+    /// Events always redraw, so only the changes made here on their own ask
+    /// for a frame: reloaded or fading variables and expired messages.
+    fn on_tick(&mut self) {
+        if self.watch {
+            if let Err(err) = self.env_list.watch(&self.search, &self.bookmarks) {
+                self.message = Some(Message::Error(format!("Could not reload: {err}")));
+                self.redraw = true;
+            }
+            // the list is dirty after a reload, and flashes fade every tick.
+            if self.env_list.dirty || !self.env_list.flashes.is_empty() {
+                self.redraw = true;
+            }
+        }
+        if self.message.is_none() {
+            self.message_shown = None;
+        } else if self
+            .message_shown
+            .get_or_insert_with(Instant::now)
+            .elapsed()
+            >= MESSAGE_TIMEOUT
+        {
+            self.message = None;
+            self.redraw = true;
         }
-        Ok(())
     }
 
-    fn on_key_event(&mut self, key: KeyEvent) {
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Esc) => self.quit(),
-            (_, KeyCode::Char('q')) => self.quit(),
-            (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.quit(),
-            (_, KeyCode::Char('h') | KeyCode::Left) => self.select_none(),
-            (_, KeyCode::Char('l') | KeyCode::Right) => self.select_none(),
-            (_, KeyCode::Char('k') | KeyCode::Up) => self.select_previous(),
-            (_, KeyCode::Char('j') | KeyCode::Down) => self.select_next(),
-            (_, KeyCode::Char('g') | KeyCode::PageUp) => self.select_first(),
-            (_, KeyCode::Char('G') | KeyCode::PageDown) => self.select_last(),
-            _ => {}
+    fn clear_search(&mut self) {
+        self.search.clear();
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+    }
+
+    /// Flip case sensitivity and re-run the current filter.
+    fn toggle_case(&mut self) {
+        self.search.toggle_case();
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+    }
+
+    /// Re-run the search against the list after its query or options changed.
+    fn refilter(&mut self) {
+        self.search.update();
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+        // like in an editor, the selection moves to a match while typing.
+        let on_hit = self
+            .env_list
+            .state
+            .selected()
+            .is_some_and(|position| self.env_list.is_hit(position));
+        if self.search.jump && !on_hit {
+            self.env_list.jump_to_hit(true);
         }
     }
 
+    /// Select the next search match, or the previous one unless `forward`.
+    fn next_hit(&mut self, forward: bool) {
+        if !self.search.jump || self.search.query.is_empty() {
+            return;
+        }
+        if !self.env_list.jump_to_hit(forward) {
+            self.message = Some(Message::Error(format!(
+                "No variables match '{}'",
+                self.search.query
+            )));
+        }
+    }
+
+    /// Open a prompt for the prefix keys must start with.
+    fn edit_prefix(&mut self) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::Prefix,
+            input: Input::new(self.search.prefix.clone()),
+        });
+    }
+
+    /// Show only the keys starting with `prefix`, or all keys if it is empty.
+    fn set_prefix(&mut self, prefix: String) {
+        self.search.prefix = prefix;
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+    }
+
+    fn toggle_reveal(&mut self) {
+        if let Some(item) = self.value_item().cloned() {
+            self.masking.toggle_reveal(&item);
+            // the table shows the revealed value.
+            self.env_list.dirty = true;
+        }
+    }
+
+    fn toggle_masking(&mut self) {
+        self.masking.enabled = !self.masking.enabled;
+        self.mask_search();
+        self.referrers = None;
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+        self.env_list.dirty = true;
+    }
+
+    /// Keep a value search from matching the secrets while they are masked.
+    fn mask_search(&mut self) {
+        self.search.masked = self.masking.enabled.then(|| {
+            let masking = self.masking.clone();
+            Box::new(move |key: &str| masking.is_secret(key)) as KeyFilter
+        });
+    }
+
+    /// Show the values in the list in place of the keys, or the keys again.
+    fn toggle_list_values(&mut self) {
+        self.list_values = !self.list_values;
+        self.env_list.dirty = true;
+    }
+
+    /// Switch to the next built-in theme.
+    fn cycle_theme(&mut self) {
+        if !self.color {
+            self.message = Some(Message::Error("Colors are turned off".to_string()));
+            return;
+        }
+        let current = self.theme_name.as_deref().unwrap_or(Theme::NAMES[0]);
+        let index = Theme::NAMES.iter().position(|&name| name == current);
+        let name = Theme::NAMES[index.map_or(0, |i| (i + 1) % Theme::NAMES.len())];
+        if let Some(theme) = Theme::builtin(name) {
+            self.theme = theme;
+            self.theme_name = Some(name.to_string());
+            self.env_list.dirty = true;
+            self.message = Some(Message::Info(format!("Theme: {name}")));
+        }
+    }
+
+    /// The variable shown in the value pane: the pinned one if it is still
+    /// there, otherwise the selected one.
+    fn value_item(&self) -> Option<&Environment> {
+        let pinned = self
+            .pinned
+            .as_ref()
+            .and_then(|key| self.env_list.items.iter().find(|item| &item.key == key));
+        pinned.or_else(|| self.env_list.selected())
+    }
+
+    /// Keep the value pane on the selected variable, or let it follow the
+    /// selection again.
+    fn toggle_pin(&mut self) {
+        if let Some(key) = self.pinned.take() {
+            self.on_selection_change();
+            self.message = Some(Message::Info(format!("Unpinned {key}")));
+            return;
+        }
+        let Some(key) = self.env_list.selected().map(|item| item.key.clone()) else {
+            return;
+        };
+        self.message = Some(Message::Info(format!(
+            "Pinned {key}, the value pane stays on it"
+        )));
+        self.pinned = Some(key);
+    }
+
+    /// Hide the value pane to give the list the whole main area, or show it
+    /// again.
+    fn toggle_value_pane(&mut self) {
+        self.value_pane = !self.value_pane;
+        if !self.value_pane {
+            self.focus = Focus::List;
+        }
+    }
+
+    /// Give the list `step` percent more of the main area, or less if negative.
+    fn resize_split(&mut self, step: i16) {
+        self.split = self
+            .split
+            .saturating_add_signed(step)
+            .clamp(MIN_SPLIT, MAX_SPLIT);
+    }
+
+    /// Switch between the list of keys and the table of keys and values.
+    fn toggle_columns(&mut self) {
+        self.columns = !self.columns;
+        self.env_list.dirty = true;
+    }
+
+    /// Quit, asking first if there are edits that would be lost.
     fn quit(&mut self) {
-        self.is_running = false;
+        if self.env_list.edited && self.original.is_none() {
+            self.overlay = Some(Overlay::ConfirmQuit);
+        } else {
+            self.is_running = false;
+        }
     }
-}
 
-impl Widget for &mut App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let [header_area, main_area, footer_area] = Layout::vertical([
-            Constraint::Length(2),
-            Constraint::Fill(1),
-            Constraint::Length(1),
-        ])
-        .areas(area);
+    /// Quit to start a shell with the variables, edits included.
+    fn quit_to_shell(&mut self) {
+        if self.can_edit() {
+            self.relaunch = true;
+            self.is_running = false;
+        }
+    }
 
-        let [list_area, item_area] =
-            Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(main_area);
+    /// The shell to start after quitting with `Q`, `$SHELL` or the command
+    /// interpreter of the platform, with the variables as its environment.
+    ///
+    /// Removed and deleted variables are unset, and values that were not
+    /// valid UTF-8 are inherited unchanged.
+    pub fn shell_command(&self) -> Option<Command> {
+        if !self.relaunch {
+            return None;
+        }
+        let program = env::var_os("SHELL")
+            .or_else(|| env::var_os("COMSPEC"))
+            .unwrap_or_else(|| "sh".into());
+        let mut command = Command::new(program);
+        let kept: Vec<&Environment> = self
+            .env_list
+            .items
+            .iter()
+            .filter(|item| item.status != Some(DiffStatus::Removed))
+            .collect();
+        let keys: HashSet<&str> = kept.iter().map(|item| item.key.as_str()).collect();
+        for (key, _) in env::vars_os() {
+            if !keys.contains(key.to_string_lossy().as_ref()) {
+                command.env_remove(key);
+            }
+        }
+        command.envs(
+            kept.iter()
+                .filter(|item| !item.lossy)
+                .map(|item| (&item.key, &item.value)),
+        );
+        Some(command)
+    }
 
-        App::render_header(header_area, buf);
-        App::render_footer(footer_area, buf);
-        self.render_list(list_area, buf);
-        self.render_selected_item(item_area, buf);
+    /// With [`App::evaluating`], write the statements setting the new and
+    /// changed variables and unsetting the removed ones to `out`, for
+    /// `eval "$(envirust --eval)"`.
+    ///
+    /// The values are quoted for the shell picked with `--shell` and never
+    /// redacted. Keys the shell would not read as names are left out and
+    /// reported to `errors`, since evaluating them could run commands.
+    pub fn write_eval(&self, out: &mut impl Write, errors: &mut impl Write) -> io::Result<()> {
+        let Some(original) = &self.original else {
+            return Ok(());
+        };
+        let (lines, skipped) = eval_lines(original, &self.env_list.snapshot(), self.shell);
+        for line in lines {
+            writeln!(out, "{line}")?;
+        }
+        for key in skipped {
+            writeln!(
+                errors,
+                "envirust: skipped {key:?}, not a valid variable name for {}",
+                self.shell.name()
+            )?;
+        }
+        Ok(())
     }
 }
 
 impl App {
-    fn render_header(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Ratatui Environment Reader")
-            .bold()
-            .centered()
-            .render(area, buf);
+    /// Keep the selected variable in the register and copy it as `KEY=VALUE`.
+    fn yank(&mut self) {
+        let Some(item) = self.env_list.selected().cloned() else {
+            return;
+        };
+        let key = item.key.clone();
+        let copied = self.copy_to_clipboard(item.to_string());
+        self.register = Some(item);
+        self.message = Some(match copied {
+            Ok(()) => Message::Info(format!("Yanked {key} and copied it as KEY=VALUE")),
+            Err(err) => Message::Error(format!("Yanked {key}, clipboard unavailable: {err}")),
+        });
     }
 
-    fn render_footer(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Use ↓↑ or 'jk', 'gG' to move, and <Esc>, Ctrl-c or 'q' to quit")
-            .centered()
-            .render(area, buf);
+    /// Add the variable in the register back, replacing the value of one
+    /// with the same key.
+    fn put(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        let Some(item) = self.register.clone() else {
+            self.message = Some(Message::Error("Nothing yanked or deleted yet".into()));
+            return;
+        };
+        self.set_value(&item.key, item.value);
+        self.message = Some(Message::Info(format!("Put {}", item.key)));
     }
 
-    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let block = Block::new()
-            .title(Line::raw("Environment List").centered())
-            .borders(Borders::TOP)
-            .border_set(symbols::border::EMPTY)
-            .border_style(Style::new().fg(SLATE.c100).bg(BLUE.c800))
-            .bg(SLATE.c950);
+    /// Open the path in the shown value with the default app of the
+    /// platform, a file manager for directories.
+    fn open_path(&mut self) {
+        let Some(item) = self.value_item() else {
+            return;
+        };
+        let path = Path::new(&item.value);
+        if !ui::looks_like_path(&item.value) || ui::PathKind::of(path) == ui::PathKind::Missing {
+            self.message = Some(Message::Error("Not a path".to_string()));
+            return;
+        }
+        let path = ui::expand_home(path).into_owned();
+        self.message = Some(match opener::open(&path) {
+            Ok(()) => Message::Info(format!("Opened {}", path.display())),
+            Err(err) => Message::Error(format!("Could not open {}: {err}", path.display())),
+        });
+    }
+
+    /// Copy the selected `KEY=VALUE` pair to the system clipboard.
+    fn copy_pair(&mut self) {
+        if let Some(pair) = self.env_list.selected().map(|item| item.to_string()) {
+            self.copy(pair, "pair");
+        }
+    }
 
-        let items: Vec<ListItem> = self
+    /// Copy the selected value quoted for the export shell, so it can be
+    /// pasted into a command as one word.
+    fn copy_quoted(&mut self) {
+        if let Some(value) = self
+            .env_list
+            .selected()
+            .map(|item| self.shell.quote(&item.value).into_owned())
+        {
+            self.copy(value, "quoted value");
+        }
+    }
+
+    /// Copy the shown variables as `KEY=VALUE` lines, with secrets
+    /// redacted like in exports.
+    fn copy_shown(&mut self) {
+        let shown = self
+            .picked()
+            .unwrap_or_else(|| self.env_list.shown().cloned().collect());
+        if shown.is_empty() {
+            return;
+        }
+        let lines: Vec<String> = self
+            .exported(&shown, self.include_secrets)
+            .iter()
+            .map(|item| format!("{item}\n"))
+            .collect();
+        let what = match shown.len() {
+            1 => "1 variable".to_string(),
+            n => format!("{n} variables"),
+        };
+        self.copy(lines.concat(), &what);
+    }
+
+    /// Copy `text` and report the outcome in the footer.
+    fn copy(&mut self, text: String, what: &str) {
+        self.message = Some(match self.copy_to_clipboard(text) {
+            Ok(()) => Message::Info(format!("Copied {what}")),
+            Err(err) => Message::Error(format!("Clipboard unavailable: {err}")),
+        });
+    }
+
+    /// Write all variables to the export path as a `.env` file.
+    fn export_dotenv(&mut self) {
+        self.start_export(Export::Dotenv);
+    }
+
+    /// Write all variables to the export path as a JSON object.
+    fn export_json(&mut self) {
+        self.start_export(Export::Json);
+    }
+
+    /// Write all variables to a snapshot named after the time, next to the
+    /// export path.
+    fn export_snapshot(&mut self) {
+        self.start_export(Export::Snapshot);
+    }
+
+    /// Write the selected variable to the export path, with the extension
+    /// of the shell, as a line the shell can source.
+    fn export_selected(&mut self) {
+        self.start_export(Export::Selected);
+    }
+
+    /// The variables written by `export`.
+    fn export_items(&self, export: Export) -> Vec<Environment> {
+        match (export, self.picked()) {
+            (Export::Snapshot, _) => self.env_list.snapshot(),
+            (_, Some(picked)) => picked,
+            (Export::Dotenv | Export::Json, None) => self.env_list.items.clone(),
+            (Export::Selected, None) => self.env_list.selected().cloned().into_iter().collect(),
+        }
+    }
+
+    /// The selected variables in list order, if any are selected.
+    fn picked(&self) -> Option<Vec<Environment>> {
+        let picked: Vec<Environment> = self
             .env_list
             .items
             .iter()
-            .map(|item| ListItem::from(item.key.clone()))
+            .filter(|item| self.selection.contains(&item.key))
+            .cloned()
             .collect();
+        (!picked.is_empty()).then_some(picked)
+    }
 
-        let list = List::new(items)
-            .block(block)
-            .highlight_style(Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD))
-            .highlight_symbol(">")
-            .highlight_spacing(HighlightSpacing::Always);
+    /// Add the variable under the cursor to the selection, or take it out.
+    fn toggle_picked(&mut self) {
+        let Some(key) = self.env_list.selected().map(|item| item.key.clone()) else {
+            return;
+        };
+        if !self.selection.remove(&key) {
+            self.selection.insert(key);
+        }
+        self.env_list.dirty = true;
+    }
 
-        StatefulWidget::render(list, area, buf, &mut self.env_list.state);
+    /// Open a prompt for a regex selecting the shown variables by key.
+    fn select_matching(&mut self) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::SelectMatching,
+            input: Input::default(),
+        });
     }
 
-    fn render_selected_item(&self, area: Rect, buf: &mut Buffer) {
-        let info = if let Some(i) = self.env_list.state.selected() {
-            self.env_list.items[i].value.clone()
-        } else {
-            "Nothing selected".to_string()
+    /// Add the shown variables with keys matching `pattern` to the selection.
+    fn add_matching(&mut self, pattern: &str) {
+        let regex = match Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(err) => {
+                self.message = Some(Message::Error(format!("Invalid regex: {err}")));
+                return;
+            }
         };
+        let keys: Vec<String> = self
+            .env_list
+            .shown()
+            .filter(|item| regex.is_match(&item.key))
+            .map(|item| item.key.clone())
+            .collect();
+        let count = keys.len();
+        self.selection.extend(keys);
+        self.env_list.dirty = true;
+        self.message = Some(Message::Info(format!(
+            "Selected {count} matching variables, {} in all",
+            self.selection.len()
+        )));
+    }
+
+    /// Deselect all variables.
+    fn clear_picked(&mut self) {
+        if self.selection.is_empty() {
+            return;
+        }
+        self.selection.clear();
+        self.env_list.dirty = true;
+        self.message = Some(Message::Info("Cleared the selection".into()));
+    }
+
+    /// `items` with the values of secrets redacted, unless `secrets` are
+    /// included or masking is off.
+    fn exported(&self, items: &[Environment], secrets: bool) -> Vec<Environment> {
+        items
+            .iter()
+            .map(|item| {
+                let mut item = item.clone();
+                if !secrets && self.is_redacted(&item) {
+                    item.value = REDACTED.to_string();
+                }
+                item
+            })
+            .collect()
+    }
+
+    fn is_redacted(&self, item: &Environment) -> bool {
+        self.masking.enabled && self.masking.is_secret(&item.key)
+    }
+
+    /// Run `export`, first asking whether secrets are written in clear text.
+    fn start_export(&mut self, export: Export) {
+        let items = self.export_items(export);
+        if items.is_empty() {
+            return;
+        }
+        let secrets = items.iter().filter(|item| self.is_redacted(item)).count();
+        if secrets > 0 && !self.include_secrets {
+            self.overlay = Some(Overlay::ConfirmExport { export, secrets });
+        } else {
+            self.run_export(export, true);
+        }
+    }
 
-        let block = Block::new()
-            .title(Line::raw("Value").centered())
-            .borders(Borders::TOP)
-            .border_set(symbols::border::EMPTY)
-            .border_style(Style::new().fg(SLATE.c100).bg(BLUE.c800))
-            .bg(SLATE.c950)
-            .padding(Padding::horizontal(1));
+    /// Write the variables of `export`, with the values of secrets unless
+    /// redacted.
+    fn run_export(&mut self, export: Export, secrets: bool) {
+        let original = self.export_items(export);
+        let items = self.exported(&original, secrets);
+        let redacted = items
+            .iter()
+            .zip(&original)
+            .filter(|(item, original)| item.value != original.value)
+            .count();
+        let (path, result) = match export {
+            Export::Dotenv => {
+                let path = self.export_path.clone();
+                let result = write_dotenv(&items, &path);
+                (path, result)
+            }
+            Export::Json => {
+                let path = self.export_path.with_extension("json");
+                let result = write_json(&items, &path).map(|()| Vec::new());
+                (path, result)
+            }
+            Export::Selected => {
+                let path = self.export_path.with_extension(self.shell.extension());
+                let result = write_export(&items, self.shell, &path).map(|()| Vec::new());
+                (path, result)
+            }
+            Export::Snapshot => {
+                let taken = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let name = format!("envirust-snapshot-{}.json", utc_timestamp(taken));
+                let path = self.export_path.with_file_name(name);
+                let result = write_snapshot(&items, &path, taken).map(|()| Vec::new());
+                (path, result)
+            }
+        };
+        let written = items.len() - result.as_ref().map_or(0, Vec::len);
+        let what = match items.as_slice() {
+            [item] if export == Export::Selected => item.key.clone(),
+            _ => format!("{written} variables"),
+        };
+        self.message = Some(match result {
+            Ok(skipped) if !skipped.is_empty() => Message::Error(format!(
+                "Exported {what} to {}, skipped {}: not valid variable names",
+                path.display(),
+                skipped
+                    .iter()
+                    .map(|key| format!("{key:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Ok(_) if redacted > 0 => Message::Info(format!(
+                "Exported {what} to {}, {redacted} secrets redacted",
+                path.display()
+            )),
+            Ok(_) => Message::Info(format!("Exported {what} to {}", path.display())),
+            Err(err) => Message::Error(format!("Could not write {}: {err}", path.display())),
+        });
+    }
 
-        Paragraph::new(info)
-            .block(block)
-            .fg(SLATE.c200)
-            .wrap(Wrap { trim: false })
-            .render(area, buf);
+    fn copy_to_clipboard(&mut self, text: String) -> std::result::Result<(), arboard::Error> {
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self.clipboard.insert(Clipboard::new()?),
+        };
+        clipboard.set_text(text)
     }
 }
 
@@ -206,19 +1651,463 @@ impl App {
         self.env_list.state.select(None);
     }
 
+    /// Leave the value pane, or clear the selection when the list has focus.
+    fn step_out(&mut self) {
+        match self.focus {
+            Focus::Value => self.focus = Focus::List,
+            Focus::List => self.select_none(),
+        }
+    }
+
+    /// Move focus into the value pane of the selected variable.
+    fn step_in(&mut self) {
+        if self.value_pane && self.value_item().is_some() {
+            self.focus = Focus::Value;
+        }
+    }
+
+    // the movements do nothing on an empty list, leaving nothing selected.
     fn select_next(&mut self) {
-        self.env_list.state.select_next();
+        self.move_selection(true, 1);
     }
 
     fn select_previous(&mut self) {
-        self.env_list.state.select_previous();
+        match self.env_list.state.selected() {
+            Some(_) => self.move_selection(false, 1),
+            None => self.select_last(),
+        }
+    }
+
+    /// Select the variable at the 1-based `position`, or the last one if
+    /// there are fewer.
+    fn select_position(&mut self, position: usize) {
+        if let Some(last) = self.env_list.view.len().checked_sub(1) {
+            let index = position.saturating_sub(1).min(last);
+            self.env_list.state.select(Some(index));
+        }
     }
 
     fn select_first(&mut self) {
-        self.env_list.state.select_first();
+        if !self.env_list.view.is_empty() {
+            self.env_list.state.select_first();
+        }
     }
 
+    // the list is drawn from a window of its rows, so the selection is
+    // kept in range here rather than by the list widget.
     fn select_last(&mut self) {
-        self.env_list.state.select_last();
+        if let Some(last) = self.env_list.view.len().checked_sub(1) {
+            self.env_list.state.select(Some(last));
+        }
+    }
+
+    /// Move the selection `n` items down or up, stopping at the ends.
+    fn move_selection(&mut self, down: bool, n: usize) {
+        let Some(last) = self.env_list.view.len().checked_sub(1) else {
+            return;
+        };
+        let index = match self.env_list.state.selected() {
+            Some(i) if down => i.saturating_add(n).min(last),
+            Some(i) => i.saturating_sub(n),
+            None => 0,
+        };
+        self.env_list.state.select(Some(index));
+    }
+
+    /// Open a prompt to edit the selected value.
+    fn edit_value(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        let Some(item) = self.env_list.selected() else {
+            return;
+        };
+        self.prompt = Some(Prompt {
+            kind: PromptKind::EditValue {
+                key: item.key.clone(),
+            },
+            input: Input::new(item.value.clone()),
+        });
+    }
+
+    /// Whether variables can be changed, reporting why not in the footer.
+    fn can_edit(&mut self) -> bool {
+        if !self.env_list.source.editable() {
+            self.message = Some(Message::Error(
+                "Editing is not available in diff mode".into(),
+            ));
+            return false;
+        }
+        true
+    }
+
+    /// Open a prompt for the key of a new variable.
+    fn add_variable(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        self.prompt = Some(Prompt {
+            kind: PromptKind::NewKey,
+            input: Input::default(),
+        });
+    }
+
+    /// Set the variable `key` to `value`, at the source of the variables too.
+    fn set_value(&mut self, key: &str, value: String) {
+        self.env_list.source.set_var(key, &value);
+        self.env_list
+            .set_value(key, value, &self.search, &self.bookmarks);
+        self.message = Some(Message::Info(format!("Set {key}")));
+    }
+
+    /// Bookmark the selected variable, or remove its bookmark.
+    fn toggle_bookmark(&mut self) {
+        let Some(key) = self.env_list.selected().map(|item| item.key.clone()) else {
+            return;
+        };
+        let bookmarked = self.bookmarks.toggle(&key);
+        self.env_list.dirty = true;
+        if self.bookmarks.only {
+            self.env_list.apply_filter(&self.search, &self.bookmarks);
+        }
+        self.message = Some(match self.bookmarks.save() {
+            Ok(()) if bookmarked => Message::Info(format!("Bookmarked {key}")),
+            Ok(()) => Message::Info(format!("Removed the bookmark on {key}")),
+            Err(err) => Message::Error(format!("Could not save the bookmarks: {err}")),
+        });
+    }
+
+    /// Switch between showing all variables and only the bookmarked ones.
+    fn toggle_bookmarks_only(&mut self) {
+        self.bookmarks.only = !self.bookmarks.only;
+        self.env_list.apply_filter(&self.search, &self.bookmarks);
+    }
+
+    /// Start marking the changes made from now on, or stop marking them.
+    fn toggle_baseline(&mut self) {
+        if self.env_list.baseline.is_some() {
+            self.env_list
+                .set_baseline(None, &self.search, &self.bookmarks);
+            self.message = Some(Message::Info("Stopped marking changes".into()));
+            return;
+        }
+        if !self.env_list.source.editable() {
+            self.message = Some(Message::Error(
+                "Baselines are not available in diff mode".into(),
+            ));
+            return;
+        }
+        let baseline = Baseline {
+            origin: "snapshot".into(),
+            items: self.env_list.snapshot(),
+        };
+        self.env_list
+            .set_baseline(Some(baseline), &self.search, &self.bookmarks);
+        self.message = Some(Message::Info(
+            "Marking the variables changed from now on".into(),
+        ));
+    }
+
+    /// Ask before deleting the selected variables, or the one under the
+    /// cursor without a selection.
+    fn confirm_delete(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        let keys: Vec<String> = match self.picked() {
+            Some(picked) => picked.into_iter().map(|item| item.key).collect(),
+            None => self
+                .env_list
+                .selected()
+                .map(|item| item.key.clone())
+                .into_iter()
+                .collect(),
+        };
+        if !keys.is_empty() {
+            self.overlay = Some(Overlay::ConfirmDelete { keys });
+        }
+    }
+
+    /// Remove the variables `keys`, the last one going into the register.
+    fn delete_variables(&mut self, keys: &[String]) {
+        for key in keys {
+            self.delete_variable(key);
+            self.selection.remove(key);
+        }
+        if let [_, _, ..] = keys {
+            self.message = Some(Message::Info(format!(
+                "Deleted {} variables, 'p' puts back the last",
+                keys.len()
+            )));
+        }
+    }
+
+    /// Remove the variable `key`, from the source of the variables too.
+    fn delete_variable(&mut self, key: &str) {
+        self.register = self
+            .env_list
+            .items
+            .iter()
+            .find(|item| item.key == key)
+            .cloned();
+        self.env_list.source.remove_var(key);
+        self.env_list.remove(key, &self.search, &self.bookmarks);
+        self.message = Some(Message::Info(format!("Deleted {key}, 'p' puts it back")));
+    }
+
+    /// Read the variables again, keeping the selection where possible.
+    fn reload(&mut self) {
+        self.message = Some(match self.env_list.reload(&self.search, &self.bookmarks) {
+            Ok(()) => Message::Info(format!("Reloaded {} variables", self.env_list.items.len())),
+            Err(err) => Message::Error(format!("Could not reload: {err}")),
+        });
+    }
+
+    /// Toggle showing the selected value base64-decoded.
+    fn toggle_decode(&mut self) {
+        if self.decoded {
+            self.decoded = false;
+            return;
+        }
+        let Some(item) = self.value_item() else {
+            return;
+        };
+        if decode_base64(&item.value).is_some() {
+            self.decoded = true;
+        } else {
+            self.message = Some(Message::Error("not valid base64".to_string()));
+        }
+    }
+
+    fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.env_list.value_hscroll = 0;
+    }
+
+    /// Scroll the value pane left, when not wrapping.
+    fn scroll_value_left(&mut self) {
+        self.env_list.value_hscroll = self.env_list.value_hscroll.saturating_sub(HSCROLL_STEP);
+    }
+
+    /// Scroll the value pane right, when not wrapping.
+    fn scroll_value_right(&mut self) {
+        if !self.wrap {
+            self.env_list.value_hscroll = self.env_list.value_hscroll.saturating_add(HSCROLL_STEP);
+        }
+    }
+
+    /// Scroll the value pane by one mouse wheel step.
+    fn wheel_value(&mut self, down: bool) {
+        self.scroll_value(down, WHEEL_STEP);
+    }
+
+    /// Scroll the value pane by `lines`.
+    fn scroll_value(&mut self, down: bool, lines: u16) {
+        let scroll = &mut self.env_list.value_scroll;
+        *scroll = if down {
+            scroll.saturating_add(lines)
+        } else {
+            scroll.saturating_sub(lines)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    /// An app showing `items`, with the defaults rather than the config and
+    /// state of whoever runs the tests.
+    pub(super) fn app_with(items: Vec<Environment>) -> App {
+        App::with_settings(
+            EnvList::new(Box::new(items)).unwrap(),
+            Settings::default(),
+            UiState::default(),
+        )
+    }
+
+    pub(super) fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.on_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    pub(super) fn selected_key(app: &App) -> Option<&str> {
+        app.env_list.selected().map(|item| item.key.as_str())
+    }
+
+    #[test]
+    fn movement_stops_at_the_ends() {
+        let mut app = app_with(vec![
+            Environment::new("A".into(), String::new()),
+            Environment::new("B".into(), String::new()),
+        ]);
+        app.select_previous();
+        app.select_next();
+        assert_eq!(selected_key(&app), Some("B"));
+        app.select_first();
+        app.select_previous();
+        assert_eq!(selected_key(&app), Some("A"));
+    }
+
+    #[test]
+    fn secret_patterns_can_be_extended_or_replaced() {
+        let (masking, warnings) =
+            Masking::from_toml("[masking]\npatterns = [\"*_PIN\"]\nregexes = [\"^aws_\"]");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(masking.is_secret("GITHUB_TOKEN"));
+        assert!(masking.is_secret("card_pin"));
+        assert!(masking.is_secret("AWS_ACCESS_KEY_ID"));
+        assert!(!masking.is_secret("HOME"));
+
+        let (masking, warnings) =
+            Masking::from_toml("[masking]\ndefaults = false\nregexes = [\"(\"]");
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(!masking.is_secret("GITHUB_TOKEN"));
+    }
+
+    #[test]
+    fn exports_redact_secrets_unless_included() {
+        let items = vec![
+            Environment::new("API_TOKEN".into(), "hunter2".into()),
+            Environment::new("HOME".into(), "/home/user".into()),
+        ];
+        let json = app_with(items.clone()).to_json().unwrap();
+        assert!(
+            json.contains("<redacted>") && !json.contains("hunter2"),
+            "{json}"
+        );
+        assert!(json.contains("/home/user"), "{json}");
+
+        let json = app_with(items.clone())
+            .including_secrets()
+            .to_json()
+            .unwrap();
+        assert!(json.contains("hunter2"), "{json}");
+
+        // printed output follows the same rule as exports from the TUI.
+        let mut app = app_with(items);
+        let mut out = Vec::new();
+        app.write_list(&mut out).unwrap();
+        let list = String::from_utf8(out).unwrap();
+        assert!(list.contains("API_TOKEN=<redacted>"), "{list}");
+        assert_eq!(app.redacted_count(), 1);
+        assert_eq!(
+            app.export_items(Export::Dotenv)
+                .iter()
+                .filter(|item| app.is_redacted(item))
+                .count(),
+            1
+        );
+
+        press(&mut app, "m");
+        let mut out = Vec::new();
+        app.write_list(&mut out).unwrap();
+        let list = String::from_utf8(out).unwrap();
+        assert!(list.contains("API_TOKEN=hunter2"), "{list}");
+        assert_eq!(app.redacted_count(), 0);
+        assert!(
+            !app.export_items(Export::Dotenv)
+                .iter()
+                .any(|item| app.is_redacted(item))
+        );
+    }
+
+    #[test]
+    fn masked_secrets_are_not_matched_by_value() {
+        let mut app = app_with(vec![
+            Environment::new("API_TOKEN".into(), "hunter2".into()),
+            Environment::new("COPY".into(), "hunter2".into()),
+        ]);
+        press(&mut app, "/");
+        app.on_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        press(&mut app, "hunter");
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let shown: Vec<&str> = app.env_list.shown().map(|item| item.key.as_str()).collect();
+        assert_eq!(shown, ["COPY"]);
+
+        press(&mut app, "m");
+        assert_eq!(app.env_list.shown().count(), 2);
+    }
+
+    #[test]
+    fn only_existing_paths_are_opened() {
+        let mut app = app_with(vec![
+            Environment::new("LANG".into(), "C.UTF-8".into()),
+            Environment::new("GONE".into(), "/envirust/missing".into()),
+        ]);
+        for keys in ["jo", "jo"] {
+            press(&mut app, keys);
+            assert!(
+                matches!(&app.message, Some(Message::Error(error)) if error == "Not a path"),
+                "{keys}"
+            );
+        }
+    }
+
+    #[test]
+    fn highlight_symbol_and_spacing_come_from_the_config() {
+        let (highlight, warnings) = Highlight::from_toml(
+            "[list]\nhighlight_symbol = \"▶ \"\nhighlight_spacing = \"when_selected\"",
+        );
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(highlight.symbol, "▶ ");
+        assert_eq!(highlight.spacing, HighlightSpacing::WhenSelected);
+
+        let (_, warnings) = Highlight::from_toml("[list]\nhighlight_spacing = \"sometimes\"");
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+    }
+
+    #[test]
+    fn descriptions_can_be_added_or_removed() {
+        let (descriptions, warnings) = Descriptions::from_toml(
+            "[descriptions]\nMY_TOOL_HOME = \"where my tool lives\"\nHOME = \"\"\nBAD = 1",
+        );
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(
+            descriptions.get("MY_TOOL_HOME"),
+            Some("where my tool lives")
+        );
+        assert_eq!(descriptions.get("HOME"), None);
+        assert!(descriptions.get("PATH").is_some());
+    }
+
+    #[test]
+    fn quitting_to_a_shell_passes_the_variables_on() {
+        let mut app = app_with(vec![Environment::new("GREETING".into(), "hi".into())]);
+        assert!(app.shell_command().is_none());
+        press(&mut app, "Q");
+        assert!(!app.is_running);
+        let shell = app.shell_command().expect("a shell is started");
+        let envs: HashMap<_, _> = shell.get_envs().collect();
+        assert_eq!(
+            envs.get(std::ffi::OsStr::new("GREETING")),
+            Some(&Some(std::ffi::OsStr::new("hi")))
+        );
+        // the variables of this process are not in the list, so they were
+        // deleted and must not reach the shell.
+        for (key, _) in std::env::vars_os().filter(|(key, _)| key != "GREETING") {
+            assert_eq!(envs.get(key.as_os_str()), Some(&None), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn eval_output_leaves_out_keys_that_are_not_names() {
+        let mut app = app_with(vec![Environment::new("HOME".into(), "/root".into())]).evaluating();
+        app.env_list
+            .items
+            .push(Environment::new("X;id".into(), "1".into()));
+        app.env_list
+            .items
+            .push(Environment::new("EDITOR".into(), "vi".into()));
+        let (mut out, mut errors) = (Vec::new(), Vec::new());
+        app.write_eval(&mut out, &mut errors).unwrap();
+        let (out, errors) = (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(errors).unwrap(),
+        );
+        assert_eq!(out, "export EDITOR=vi\n");
+        assert!(errors.contains("skipped \"X;id\""), "{errors}");
     }
 }
@@ -0,0 +1,466 @@
+use super::{App, Focus, Input, Message, Operator, Overlay, Prompt, PromptKind, SPLIT_STEP};
+use crate::{
+    keymap::Action,
+    model::{Row, is_valid_key},
+};
+use color_eyre::Result;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+
+impl App {
+    /// Read and handle the next terminal event.
+    ///
+    /// Any event that can change what is shown marks the screen for a
+    /// redraw here, so the handlers do not each have to remember it.
+    pub(super) fn handle_crossterm_events(&mut self) -> Result<()> {
+        let selected = self.env_list.selected().map(|item| item.key.clone());
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.on_key_event(key);
+                self.redraw = true;
+            }
+            // moving the pointer does nothing, and reports arrive constantly.
+            Event::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+                self.on_mouse_event(mouse);
+                self.redraw = true;
+            }
+            Event::Resize(_, _) => {
+                self.resized = true;
+                self.redraw = true;
+            }
+            _ => {}
+        }
+        if self.env_list.selected().map(|item| &item.key) != selected.as_ref() {
+            self.on_selection_change();
+        }
+        Ok(())
+    }
+
+    /// Reset the per-item state once a different variable is selected.
+    pub(super) fn on_selection_change(&mut self) {
+        if self.pinned.is_some() {
+            return;
+        }
+        if self.masking.revealed.take().is_some() {
+            self.env_list.dirty = true;
+        }
+        self.env_list.value_scroll = 0;
+        self.env_list.value_hscroll = 0;
+        self.decoded = false;
+    }
+
+    pub(super) fn on_key_event(&mut self, key: KeyEvent) {
+        self.message = None;
+        self.message_shown = None;
+        if let Some(overlay) = self.overlay.take() {
+            self.on_overlay_key_event(overlay, key);
+            return;
+        }
+        if self.prompt.is_some() {
+            self.on_prompt_key_event(key);
+            return;
+        }
+        if self.search.editing {
+            self.on_search_key_event(key);
+            return;
+        }
+        if let Some(operator) = self.operator.take() {
+            // anything but the same key again cancels the command.
+            let repeated = match operator {
+                Operator::Yank => self.keymap.action(&key) == Some(Action::Copy),
+                Operator::Delete => {
+                    key.modifiers == KeyModifiers::NONE && key.code == KeyCode::Char('d')
+                }
+            };
+            match operator {
+                Operator::Yank if repeated => self.yank(),
+                Operator::Delete if repeated => self.confirm_delete(),
+                _ => {}
+            }
+            return;
+        }
+        if let KeyCode::Char(c) = key.code
+            && let Some(digit) = c.to_digit(10)
+            && !key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            && (digit > 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or_default();
+            self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+            return;
+        }
+        let count = self.count.take();
+        if key.code == KeyCode::Esc && count.is_some() {
+            return;
+        }
+        if key.code == KeyCode::Esc && !self.search.query.is_empty() {
+            self.clear_search();
+            return;
+        }
+        if key.code == KeyCode::Esc && !self.search.prefix.is_empty() {
+            self.set_prefix(String::new());
+            return;
+        }
+        if let Some(action) = self.keymap.action(&key) {
+            self.perform(action, count);
+            return;
+        }
+        match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.quit(),
+            (modifiers, KeyCode::Char(c))
+                if modifiers.contains(KeyModifiers::ALT) && self.focus == Focus::List =>
+            {
+                self.env_list.jump_to(c)
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('y')) => self.copy_quoted(),
+            (KeyModifiers::CONTROL, KeyCode::Char('p')) => self.toggle_pin(),
+            (KeyModifiers::CONTROL, KeyCode::Char('t')) => self.toggle_case(),
+            // the fixed keys only act without modifiers, like in `FIXED_KEYS`.
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, code) => match code {
+                KeyCode::Char('h') | KeyCode::Left => self.step_out(),
+                KeyCode::Char('l') | KeyCode::Right => self.step_in(),
+                KeyCode::Char('s') => self.env_list.cycle_sort(),
+                KeyCode::Char('S') => self.env_list.toggle_direction(),
+                KeyCode::Char('Y') => self.copy_pair(),
+                KeyCode::Char('A') => self.copy_shown(),
+                KeyCode::Char(' ') => self.toggle_picked(),
+                KeyCode::Char('M') => self.select_matching(),
+                KeyCode::Char('u') => self.clear_picked(),
+                KeyCode::Char('w') => self.export_dotenv(),
+                KeyCode::Char('J') => self.export_json(),
+                KeyCode::Char('W') => self.export_snapshot(),
+                KeyCode::Char('Q') => self.quit_to_shell(),
+                KeyCode::Char('E') => self.export_selected(),
+                KeyCode::Char('P') => self.edit_prefix(),
+                KeyCode::Char('m') => self.toggle_masking(),
+                KeyCode::Enter => self.toggle_reveal(),
+                KeyCode::Char('c') => self.toggle_columns(),
+                KeyCode::Char('C') => self.cycle_theme(),
+                KeyCode::Char('n') => self.next_hit(true),
+                KeyCode::Char('N') => self.next_hit(false),
+                KeyCode::Char('+') => self.resize_split(SPLIT_STEP),
+                KeyCode::Char('-') => self.resize_split(-SPLIT_STEP),
+                KeyCode::Char('D') => self.toggle_baseline(),
+                KeyCode::Char('#') => {
+                    self.line_numbers = !self.line_numbers;
+                    self.env_list.dirty = true;
+                }
+                KeyCode::Char('z') => self.toggle_wrap(),
+                KeyCode::Char('f') => self.pretty_json = !self.pretty_json,
+                KeyCode::Char('B') => self.toggle_decode(),
+                KeyCode::Char('r') => self.reload(),
+                KeyCode::Char('e') => self.edit_value(),
+                KeyCode::Char('a') => self.add_variable(),
+                KeyCode::Char('d') => self.operator = Some(Operator::Delete),
+                KeyCode::Char('p') => self.put(),
+                KeyCode::Tab if self.value_pane => self.focus = self.focus.toggle(),
+                KeyCode::Char('V') => self.toggle_value_pane(),
+                KeyCode::Char('x') => self.cross_references = !self.cross_references,
+                KeyCode::Char('X') => self.show_referrers = !self.show_referrers,
+                KeyCode::Char('K') => self.toggle_list_values(),
+                KeyCode::Char('v') => self.expand = !self.expand,
+                KeyCode::Char('R') => self.raw = !self.raw,
+                KeyCode::Char('F') => self.stat_paths = !self.stat_paths,
+                KeyCode::Char('o') => self.open_path(),
+                KeyCode::Char('U') => {
+                    self.unique_paths = !self.unique_paths;
+                    self.env_list.value_scroll = 0;
+                }
+                KeyCode::Char('?') => self.overlay = Some(Overlay::Help { scroll: 0 }),
+                KeyCode::Char('i') => self.overlay = Some(Overlay::Summary),
+                KeyCode::Char('t') => self.env_list.toggle_grouping(),
+                KeyCode::Char('T') => self.env_list.cycle_delimiter(),
+                KeyCode::Char('b') => self.toggle_bookmark(),
+                KeyCode::Char('*') => self.toggle_bookmarks_only(),
+                KeyCode::Char('H') => self.scroll_value_left(),
+                KeyCode::Char('L') => self.scroll_value_right(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn on_mouse_event(&mut self, mouse: MouseEvent) {
+        let position = (mouse.column, mouse.row).into();
+        let over_list = self.list_area.contains(position);
+        let over_value = self.value_area.contains(position);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if over_value => self.focus = Focus::Value,
+            MouseEventKind::Down(MouseButton::Left) => self.click_list(mouse.column, mouse.row),
+            MouseEventKind::ScrollDown if over_list => self.select_next(),
+            MouseEventKind::ScrollUp if over_list => self.select_previous(),
+            MouseEventKind::ScrollDown if over_value => self.wheel_value(true),
+            MouseEventKind::ScrollUp if over_value => self.wheel_value(false),
+            _ => {}
+        }
+    }
+
+    /// Select the list item drawn at the clicked cell, if any.
+    fn click_list(&mut self, column: u16, row: u16) {
+        let area = self.list_area;
+        // the first row of the list holds the block title.
+        if !area.contains((column, row).into()) || row == area.y {
+            return;
+        }
+        self.focus = Focus::List;
+        let index = usize::from(row - area.y - 1) + self.env_list.state.offset();
+        if let Some(&Row::Item { position, .. }) = self.env_list.rows().get(index) {
+            self.env_list.state.select(Some(position));
+        }
+    }
+
+    fn on_prompt_key_event(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.prompt = None,
+            (_, KeyCode::Enter) => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.submit_prompt(prompt);
+                }
+            }
+            _ => prompt.input.on_key_event(key),
+        }
+    }
+
+    fn submit_prompt(&mut self, prompt: Prompt) {
+        match prompt.kind {
+            PromptKind::EditValue { key } => self.set_value(&key, prompt.input.text),
+            PromptKind::NewKey => {
+                let key = prompt.input.text;
+                if !is_valid_key(&key) {
+                    self.message = Some(Message::Error(format!(
+                        "Invalid key '{key}', keys must be non-empty without '=' or whitespace"
+                    )));
+                    return;
+                }
+                self.prompt = Some(Prompt {
+                    kind: PromptKind::NewValue { key },
+                    input: Input::default(),
+                });
+            }
+            PromptKind::NewValue { key } => {
+                self.set_value(&key, prompt.input.text);
+                self.env_list.select_key(Some(&key));
+            }
+            PromptKind::Prefix => self.set_prefix(prompt.input.text),
+            PromptKind::SelectMatching => self.add_matching(&prompt.input.text),
+        }
+    }
+
+    /// Answer the open dialog, which stays open on any other key.
+    fn on_overlay_key_event(&mut self, mut overlay: Overlay, key: KeyEvent) {
+        match (&mut overlay, key.code) {
+            (Overlay::Help { .. }, KeyCode::Char('?' | 'q') | KeyCode::Esc) => {}
+            (Overlay::Help { scroll }, KeyCode::Char('j') | KeyCode::Down) => {
+                let last = self.help_lines().len().saturating_sub(1);
+                *scroll = scroll.saturating_add(1).min(last as u16);
+                self.overlay = Some(overlay);
+            }
+            (Overlay::Help { scroll }, KeyCode::Char('k') | KeyCode::Up) => {
+                *scroll = scroll.saturating_sub(1);
+                self.overlay = Some(overlay);
+            }
+            (Overlay::Help { .. }, _) => self.overlay = Some(overlay),
+            (Overlay::Summary, KeyCode::Char('i' | 'q') | KeyCode::Esc) => {}
+            (Overlay::ConfirmQuit, KeyCode::Char('y')) => self.is_running = false,
+            (Overlay::ConfirmDelete { keys }, KeyCode::Char('y')) => self.delete_variables(keys),
+            (&mut Overlay::ConfirmExport { export, .. }, KeyCode::Char(answer @ ('y' | 'n'))) => {
+                self.run_export(export, answer == 'y')
+            }
+            (_, KeyCode::Char('n') | KeyCode::Esc) => {}
+            _ => self.overlay = Some(overlay),
+        }
+    }
+
+    /// Perform `action`, repeating movements `count` times. With a count,
+    /// first and last jump to that position instead, like `10G`.
+    fn perform(&mut self, action: Action, count: Option<usize>) {
+        let times = count.unwrap_or(1);
+        let lines = u16::try_from(times).unwrap_or(u16::MAX);
+        match action {
+            Action::Quit => self.quit(),
+            Action::Next if self.focus == Focus::Value => self.scroll_value(true, lines),
+            Action::Previous if self.focus == Focus::Value => self.scroll_value(false, lines),
+            Action::First if self.focus == Focus::Value => self.env_list.value_scroll = 0,
+            // the value pane stops scrolling at the end of the value.
+            Action::Last if self.focus == Focus::Value => self.env_list.value_scroll = u16::MAX,
+            Action::HalfPageDown | Action::HalfPageUp | Action::PageDown | Action::PageUp
+                if self.focus == Focus::Value =>
+            {
+                let page = self.env_list.value_height;
+                let step = match action {
+                    Action::HalfPageDown | Action::HalfPageUp => page / 2,
+                    _ => page,
+                };
+                let down = matches!(action, Action::HalfPageDown | Action::PageDown);
+                self.scroll_value(down, step.max(1).saturating_mul(lines));
+            }
+            Action::HalfPageDown | Action::HalfPageUp | Action::PageDown | Action::PageUp => {
+                // the first row of the list holds the block title.
+                let page = usize::from(self.list_area.height.saturating_sub(1));
+                let step = match action {
+                    Action::HalfPageDown | Action::HalfPageUp => page / 2,
+                    _ => page,
+                };
+                let down = matches!(action, Action::HalfPageDown | Action::PageDown);
+                self.move_selection(down, step.max(1).saturating_mul(times));
+            }
+            Action::Next => (0..times).for_each(|_| self.select_next()),
+            Action::Previous => (0..times).for_each(|_| self.select_previous()),
+            Action::First | Action::Last if count.is_some() => self.select_position(times),
+            Action::First => self.select_first(),
+            Action::Last => self.select_last(),
+            Action::Search => self.search.editing = true,
+            Action::Copy => self.operator = Some(Operator::Yank),
+        }
+    }
+
+    fn on_search_key_event(&mut self, key: KeyEvent) {
+        match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.quit(),
+            (_, KeyCode::Esc) => self.clear_search(),
+            (_, KeyCode::Enter) => self.search.editing = false,
+            (_, KeyCode::Tab) => {
+                self.search.target = self.search.target.toggle();
+                self.refilter();
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('r')) => {
+                self.search.mode = self.search.mode.toggle();
+                self.refilter();
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('t')) => self.toggle_case(),
+            (KeyModifiers::CONTROL, KeyCode::Char('f')) => {
+                self.search.jump = !self.search.jump;
+                self.refilter();
+            }
+            (_, KeyCode::Backspace) => {
+                self.search.query.pop();
+                self.refilter();
+            }
+            (_, KeyCode::Char(c)) => {
+                self.search.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{app_with, press, selected_key};
+    use super::*;
+    use crate::model::Environment;
+
+    #[test]
+    fn deleted_variables_can_be_put_back() {
+        let mut app = app_with(vec![
+            Environment::new("A".into(), "1".into()),
+            Environment::new("B".into(), "2".into()),
+        ]);
+        app.env_list.state.select(Some(0));
+        // a lone d does nothing, not even the movement after it.
+        press(&mut app, "dj");
+        assert_eq!(selected_key(&app), Some("A"));
+        press(&mut app, "ddy");
+        assert_eq!(app.env_list.items.len(), 1);
+        press(&mut app, "p");
+        let put = app.env_list.items.iter().find(|item| item.key == "A");
+        assert_eq!(put.map(|item| item.value.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn n_jumps_between_matches_without_filtering() {
+        let mut app = app_with(
+            ["HOME", "PATH", "SHELL", "MANPATH", "TERM"]
+                .into_iter()
+                .map(|key| Environment::new(key.into(), String::new()))
+                .collect(),
+        );
+        press(&mut app, "/");
+        app.on_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+        press(&mut app, "PATH");
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.env_list.view.len(), 5);
+        assert_eq!(selected_key(&app), Some("PATH"));
+        press(&mut app, "n");
+        assert_eq!(selected_key(&app), Some("MANPATH"));
+        press(&mut app, "n");
+        assert_eq!(selected_key(&app), Some("PATH"));
+        press(&mut app, "N");
+        assert_eq!(selected_key(&app), Some("MANPATH"));
+    }
+
+    #[test]
+    fn pinned_values_stay_while_the_selection_moves() {
+        let mut app = app_with(vec![
+            Environment::new("A".into(), "first".into()),
+            Environment::new("B".into(), "second".into()),
+        ]);
+        let pin = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        press(&mut app, "j");
+        app.on_key_event(pin);
+        press(&mut app, "j");
+        assert_eq!(selected_key(&app), Some("B"));
+        assert_eq!(app.value_item().map(|item| item.key.as_str()), Some("A"));
+
+        app.on_key_event(pin);
+        assert_eq!(app.value_item().map(|item| item.key.as_str()), Some("B"));
+    }
+
+    #[test]
+    fn regex_selections_are_deleted_together() {
+        let mut app = app_with(vec![
+            Environment::new("AWS_REGION".into(), "eu".into()),
+            Environment::new("HOME".into(), "/home/user".into()),
+            Environment::new("AWS_PROFILE".into(), "dev".into()),
+        ]);
+        press(&mut app, "M^AWS_");
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let picked: Vec<String> = app
+            .picked()
+            .unwrap()
+            .into_iter()
+            .map(|item| item.key)
+            .collect();
+        assert_eq!(picked, ["AWS_REGION", "AWS_PROFILE"]);
+
+        press(&mut app, "jddy");
+        let keys: Vec<&str> = app.env_list.shown().map(|item| item.key.as_str()).collect();
+        assert_eq!(keys, ["HOME"]);
+        assert!(app.selection.is_empty());
+    }
+
+    #[test]
+    fn alt_letters_jump_even_when_the_letter_is_bound() {
+        let mut app = app_with(vec![
+            Environment::new("HOME".into(), "/root".into()),
+            Environment::new("SHELL".into(), "/bin/sh".into()),
+            Environment::new("USER".into(), "root".into()),
+        ]);
+        let sort = app.env_list.sort_label();
+        app.on_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT));
+        assert_eq!(selected_key(&app), Some("SHELL"));
+        assert_eq!(app.env_list.sort_label(), sort);
+
+        app.on_key_event(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::ALT));
+        assert!(app.prompt.is_none());
+        app.on_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::ALT));
+        assert_eq!(selected_key(&app), Some("USER"));
+    }
+
+    #[test]
+    fn fixed_keys_ignore_other_modifiers() {
+        let mut app = app_with(vec![Environment::new("HOME".into(), "/root".into())]);
+        let sort = app.env_list.sort;
+        app.on_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(app.env_list.sort, sort);
+        press(&mut app, "s");
+        assert_ne!(app.env_list.sort, sort);
+    }
+}
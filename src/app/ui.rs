@@ -0,0 +1,2034 @@
+use super::{App, Focus, Message, Overlay, Referrers};
+use crate::{
+    keymap::Action,
+    model::{DiffStatus, Environment, FLASH_DURATION, Row, expand_references, group_prefix},
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    symbols,
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Padding, Paragraph,
+        Row as TableRow, Scrollbar, ScrollbarOrientation, StatefulWidget, Table, TableState,
+        Widget, Wrap,
+    },
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    env, fs,
+    path::{Path, PathBuf},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// what masked values are shown as.
+const MASK: &str = "••••••";
+
+/// values shorter than this are not flagged as cross-references, since
+/// they would match almost anywhere.
+const MIN_REFERENCE_LEN: usize = 3;
+
+/// how many rows past each edge of the list are built, so moving a little
+/// does not rebuild it.
+const LIST_BUFFER: usize = 32;
+
+/// the fewest rows the list and the value pane are given, title included.
+const MIN_PANE_HEIGHT: u16 = 3;
+
+/// how many key prefixes the summary lists.
+const SUMMARY_PREFIXES: usize = 8;
+
+/// the narrowest terminal the UI is drawn in.
+const MIN_WIDTH: u16 = 16;
+
+/// how many levels of references in referenced values are expanded.
+const MAX_EXPANSION_DEPTH: usize = 8;
+
+/// the keys that cannot be rebound, listed in the help after the keymap.
+/// Each of [`crate::keymap::FIXED_KEYS`] needs an entry here.
+const FIXED_KEYS: &[(&str, &str)] = &[
+    ("h, left", "leave the value pane, or clear the selection"),
+    ("l, right", "enter the value pane"),
+    ("tab", "switch between the list and the value pane"),
+    ("ctrl-p", "pin the value pane to the variable, or unpin it"),
+    ("V", "hide or show the value pane"),
+    (
+        "alt-<letter>",
+        "jump to the next key starting with the letter",
+    ),
+    ("<count>", "repeat the next movement, like 5j or 10G"),
+    ("s", "cycle the sort mode"),
+    ("S", "reverse the sort order"),
+    ("Y", "copy the KEY=VALUE pair"),
+    ("ctrl-y", "copy the value quoted for the shell"),
+    ("A", "copy the shown variables as KEY=VALUE lines"),
+    (
+        "space",
+        "select the variable for copying, exporting and deleting",
+    ),
+    ("M", "select the shown variables with keys matching a regex"),
+    ("u", "clear the selection"),
+    ("w", "export all variables as .env"),
+    ("J", "export all variables as JSON"),
+    ("E", "export the variable as a shell line"),
+    ("W", "save a snapshot to compare against with --compare"),
+    ("m", "toggle masking secrets"),
+    ("enter", "reveal the selected secret"),
+    ("#", "toggle line numbers"),
+    ("c", "toggle showing values next to the keys"),
+    ("K", "toggle listing the values in place of the keys"),
+    ("+, -", "grow or shrink the list against the value pane"),
+    ("C", "cycle the built-in themes"),
+    ("z", "toggle wrapping the value"),
+    ("H, L", "scroll the value left and right"),
+    ("f", "toggle pretty-printing JSON"),
+    ("B", "toggle decoding base64"),
+    ("x", "toggle underlining other values found in the value"),
+    ("X", "toggle listing the variables that contain the value"),
+    ("v", "toggle resolving $VAR references"),
+    ("R", "toggle showing control chars raw"),
+    ("F", "toggle checking whether paths in the value exist"),
+    ("o", "open the path in the value with the default app"),
+    ("U", "toggle hiding repeated paths in a path list"),
+    ("t", "toggle grouping by key prefix"),
+    ("T", "cycle the grouping delimiter"),
+    ("b", "toggle a bookmark on the variable"),
+    ("*", "toggle showing only bookmarks"),
+    ("e", "edit the value"),
+    ("a", "add a variable"),
+    ("dd", "delete the variable"),
+    ("p", "put back the variable yanked or deleted last"),
+    ("r", "reload the variables"),
+    ("Q", "quit into $SHELL with the variables, edits included"),
+    ("D", "mark variables changed from now on, or stop marking"),
+    ("ctrl-t", "toggle case-sensitive search"),
+    (
+        "tab, in the search",
+        "switch between searching keys and values",
+    ),
+    (
+        "ctrl-r, in the search",
+        "switch between fuzzy and regex search",
+    ),
+    (
+        "ctrl-f, in the search",
+        "toggle jumping between matches instead of filtering",
+    ),
+    ("P", "show only keys starting with a prefix"),
+    (
+        "n, N",
+        "jump to the next or previous match, after ctrl-f in the search",
+    ),
+    ("i", "show a summary of the variables"),
+    ("?", "show this help"),
+];
+
+/// Build a text from `text`, styling the chars at `positions` with `style`.
+fn highlight(text: &str, positions: &[usize], style: Style) -> Text<'static> {
+    style_chars(text, |i| {
+        if positions.contains(&i) {
+            style
+        } else {
+            Style::new()
+        }
+    })
+}
+
+/// Replace the control chars in `text` with caret notation like `^[`, so
+/// escape sequences are shown instead of reaching the terminal. Line breaks
+/// are kept.
+fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| c.is_control() && c != '\n') {
+        return Cow::Borrowed(text);
+    }
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_visible(&mut sanitized, c);
+    }
+    Cow::Owned(sanitized)
+}
+
+/// Push `c` onto `text`, spelling out control chars other than `\n`.
+fn push_visible(text: &mut String, c: char) {
+    match c {
+        '\n' => text.push(c),
+        '\0'..='\x1f' => {
+            text.push('^');
+            text.push(char::from(c as u8 + b'@'));
+        }
+        '\x7f' => text.push_str("^?"),
+        c if c.is_control() => text.push_str(&format!("<U+{:04X}>", u32::from(c))),
+        c => text.push(c),
+    }
+}
+
+/// what a path found in a value points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PathKind {
+    File,
+    Directory,
+    Missing,
+}
+
+impl PathKind {
+    /// Stat `path`, following symlinks, with a leading `~` meaning `$HOME`.
+    pub(super) fn of(path: &Path) -> Self {
+        match fs::metadata(expand_home(path)) {
+            Ok(metadata) if metadata.is_dir() => Self::Directory,
+            Ok(_) => Self::File,
+            Err(_) => Self::Missing,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Directory => "directory",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+/// `path` with a leading `~` replaced by `$HOME`.
+pub(super) fn expand_home(path: &Path) -> Cow<'_, Path> {
+    match path.strip_prefix("~").ok().zip(env::var_os("HOME")) {
+        Some((rest, home)) => Cow::Owned(Path::new(&home).join(rest)),
+        None => Cow::Borrowed(path),
+    }
+}
+
+/// For each of `paths`, the index of its first occurrence if it is listed
+/// earlier too.
+fn repeated_paths(paths: &[PathBuf]) -> Vec<Option<usize>> {
+    let mut first: HashMap<&Path, usize> = HashMap::new();
+    paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let &mut earlier = first.entry(path).or_insert(i);
+            (earlier != i).then_some(earlier)
+        })
+        .collect()
+}
+
+/// Whether `value` looks like a single path worth checking on disk.
+pub(super) fn looks_like_path(value: &str) -> bool {
+    ["/", "./", "../", "~/"]
+        .iter()
+        .any(|start| value.starts_with(start))
+        && !value.contains('\n')
+}
+
+/// Shorten `text` to at most `width` columns, ending it with `…` if it had
+/// to be cut.
+fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    if width == 0 {
+        return Cow::Borrowed("");
+    }
+    let mut used = 0;
+    let mut end = 0;
+    for (i, c) in text.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        // leave room for the ellipsis.
+        if used + char_width + 1 > width {
+            break;
+        }
+        used += char_width;
+        end = i + c.len_utf8();
+    }
+    Cow::Owned(format!("{}…", &text[..end]))
+}
+
+/// Where the value pane is scrolled to, like `line 12-30 of 120`, once the
+/// `rows` of the value do not fit in its `height`.
+fn scroll_position(scroll: usize, height: usize, rows: usize) -> Option<String> {
+    if rows <= height || height == 0 {
+        return None;
+    }
+    let last = (scroll + height).min(rows);
+    Some(format!("line {}-{last} of {rows}", scroll + 1))
+}
+
+/// The rows `text` takes when wrapped to `width` columns by
+/// `Wrap { trim: false }`.
+///
+/// ratatui does not tell, so this follows the steps of its word wrapper with
+/// widths only: whitespace ends a word, a word that does not fit moves to the
+/// next row, and one longer than a row is broken where the row is full.
+fn wrapped_rows(text: &Text, width: u16) -> usize {
+    if width == 0 {
+        return text.lines.len();
+    }
+    text.lines
+        .iter()
+        .map(|line| {
+            let content: String = line
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            line_rows(&content, width)
+        })
+        .sum()
+}
+
+fn line_rows(line: &str, width: u16) -> usize {
+    let mut rows = 0;
+    // what is on the current row, and the word and whitespace not placed yet.
+    let (mut line_len, mut line_width) = (0, 0);
+    let (mut word_len, mut word_width) = (0, 0);
+    let mut whitespace: VecDeque<u16> = VecDeque::new();
+    let mut whitespace_width = 0;
+    let mut after_word = false;
+
+    for c in line.chars() {
+        let is_whitespace = (c.is_whitespace() && c != '\u{a0}') || c == '\u{200b}';
+        let symbol_width = c.encode_utf8(&mut [0; 4]).width() as u16;
+        // wider than a row, it is left out.
+        if symbol_width > width {
+            continue;
+        }
+
+        let overflow = line_len == 0 && word_width + whitespace_width + symbol_width > width;
+        if (after_word && is_whitespace) || overflow {
+            line_len += whitespace.len() + word_len;
+            line_width += whitespace_width + word_width;
+            whitespace.clear();
+            (whitespace_width, word_len, word_width) = (0, 0, 0);
+        }
+
+        if line_width >= width
+            || (symbol_width > 0 && line_width + whitespace_width + word_width >= width)
+        {
+            let mut remaining = width.saturating_sub(line_width);
+            rows += 1;
+            (line_len, line_width) = (0, 0);
+            // whitespace that still fits stays at the end of the row.
+            while let Some(&space) = whitespace.front().filter(|&&space| space <= remaining) {
+                whitespace_width -= space;
+                remaining -= space;
+                whitespace.pop_front();
+            }
+            if is_whitespace && whitespace.is_empty() {
+                continue;
+            }
+        }
+
+        if is_whitespace {
+            whitespace_width += symbol_width;
+            whitespace.push_back(symbol_width);
+        } else {
+            word_width += symbol_width;
+            word_len += 1;
+        }
+        after_word = !is_whitespace;
+    }
+
+    if line_len == 0 && word_len == 0 && !whitespace.is_empty() {
+        rows += 1;
+    }
+    if line_len + whitespace.len() + word_len > 0 {
+        rows += 1;
+    }
+    rows.max(1)
+}
+
+/// Build a text from `text`, styling each char with the style `style_at`
+/// returns for its index. Control chars are drawn in caret notation.
+fn style_chars(text: &str, style_at: impl Fn(usize) -> Style) -> Text<'static> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = String::new();
+    let mut style = Style::new();
+
+    for (i, c) in text.chars().enumerate() {
+        if c == '\n' {
+            if !current.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut current), style));
+            }
+            lines.push(Line::from(std::mem::take(&mut spans)));
+            continue;
+        }
+        let char_style = style_at(i);
+        if char_style != style && !current.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        style = char_style;
+        push_visible(&mut current, c);
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(current, style));
+    }
+    lines.push(Line::from(spans));
+    Text::from(lines)
+}
+
+/// The first of `len` rows to show in a list `height` rows tall, scrolled
+/// as little as possible from `offset` to keep the `selected` row in view,
+/// the same way a ratatui `List` of one-line items scrolls.
+fn list_offset(offset: usize, selected: Option<usize>, height: usize, len: usize) -> usize {
+    let offset = offset.min(len.saturating_sub(1));
+    match selected {
+        Some(selected) if selected < offset => selected,
+        Some(selected) if height > 0 && selected >= offset + height => selected + 1 - height,
+        _ => offset,
+    }
+}
+
+/// Pretty-print `value` if it holds a JSON object or array.
+fn pretty_json(value: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(value).ok()?;
+    if !json.is_object() && !json.is_array() {
+        return None;
+    }
+    serde_json::to_string_pretty(&json).ok()
+}
+
+/// Whether `value` looks like a base64-encoded blob.
+fn looks_like_base64(value: &str) -> bool {
+    let data = value.trim_end_matches('=');
+    value.len() >= 16
+        && value.len().is_multiple_of(4)
+        && value.len() - data.len() <= 2
+        && data
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+/// Decode `value` if it looks like base64.
+pub(super) fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    if !looks_like_base64(value) {
+        return None;
+    }
+    BASE64.decode(value).ok()
+}
+
+/// Format `bytes` as rows of offset, hex bytes and printable chars.
+fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {:<47}  |{ascii}|\n", row * 16, hex.join(" "))
+        })
+        .collect()
+}
+
+impl Widget for &mut App {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // the header, the status line, the footer and the title and a row of
+        // every pane.
+        let panes = if self.value_pane { 2 } else { 1 };
+        let min_height = 4 + panes * MIN_PANE_HEIGHT;
+        if area.width < MIN_WIDTH || area.height < min_height {
+            self.list_area = Rect::default();
+            self.value_area = Rect::default();
+            return self.render_too_small(area, MIN_WIDTH, min_height, buf);
+        }
+
+        let [header_area, main_area, status_area, footer_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        self.render_header(header_area, buf);
+        self.render_status(status_area, buf);
+        self.render_footer(footer_area, buf);
+        if self.value_pane {
+            // neither pane shrinks below its title and a couple of rows.
+            let height = main_area.height;
+            let list_height = (u32::from(height) * u32::from(self.split) / 100) as u16;
+            let list_height = list_height
+                .min(height.saturating_sub(MIN_PANE_HEIGHT))
+                .max(MIN_PANE_HEIGHT.min(height));
+            let [list_area, item_area] =
+                Layout::vertical([Constraint::Length(list_height), Constraint::Fill(1)])
+                    .areas(main_area);
+            self.render_list(list_area, buf);
+            self.render_selected_item(item_area, buf);
+        } else {
+            self.value_area = Rect::default();
+            self.render_list(main_area, buf);
+        }
+        if let Some(overlay) = &self.overlay {
+            self.render_overlay(overlay, area, buf);
+        }
+    }
+}
+
+/// A `width` x `height` rect in the middle of `area`.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+impl App {
+    /// Ask for a terminal of at least `width` x `height` in place of the UI.
+    fn render_too_small(&self, area: Rect, width: u16, height: u16, buf: &mut Buffer) {
+        let text = format!(
+            "Terminal too small: {}x{}, needs {width}x{height}",
+            area.width, area.height
+        );
+        let lines = text.width().div_ceil(usize::from(area.width.max(1))) as u16;
+        let [area] = Layout::vertical([Constraint::Length(lines)])
+            .flex(Flex::Center)
+            .areas(area);
+        Paragraph::new(text)
+            .fg(self.theme.error)
+            .centered()
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+
+    fn render_overlay(&self, overlay: &Overlay, area: Rect, buf: &mut Buffer) {
+        buf.set_style(
+            area,
+            Style::new()
+                .fg(self.theme.muted)
+                .add_modifier(Modifier::DIM),
+        );
+        let (title, text) = match overlay {
+            Overlay::ConfirmQuit => ("Quit", "Quit and discard your edits? (y/n)".to_string()),
+            Overlay::ConfirmDelete { keys } => match keys.as_slice() {
+                [key] => ("Delete", format!("Delete {key}? (y/n)")),
+                keys => ("Delete", format!("Delete {} variables? (y/n)", keys.len())),
+            },
+            Overlay::ConfirmExport { secrets, .. } => (
+                "Export",
+                format!("Write {secrets} secret values in clear text? (y/n, <Esc> to cancel)"),
+            ),
+            Overlay::Help { scroll } => return self.render_help(*scroll, area, buf),
+            Overlay::Summary => return self.render_summary(area, buf),
+        };
+        let area = centered(area, text.width() as u16 + 4, 3);
+        let block = Block::bordered()
+            .title(Line::raw(title).centered())
+            .border_style(self.title_bar())
+            .bg(self.theme.bg);
+        Clear.render(area, buf);
+        Paragraph::new(text)
+            .block(block)
+            .fg(self.theme.fg)
+            .centered()
+            .render(area, buf);
+    }
+
+    /// The key bindings as pairs of keys and what they do, the rebindable
+    /// ones taken from the keymap.
+    pub(super) fn help_lines(&self) -> Vec<(String, &'static str)> {
+        let mut lines: Vec<(String, &str)> = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys: Vec<String> = self
+                    .keymap
+                    .keys(action)
+                    .map(|key| key.to_string())
+                    .collect();
+                (keys.join(", "), action.description())
+            })
+            .collect();
+        lines.extend(
+            FIXED_KEYS
+                .iter()
+                .map(|&(keys, description)| (keys.to_string(), description)),
+        );
+        lines
+    }
+
+    fn render_help(&self, scroll: u16, area: Rect, buf: &mut Buffer) {
+        let help = self.help_lines();
+        let keys_width = help
+            .iter()
+            .map(|(keys, _)| keys.width())
+            .max()
+            .unwrap_or_default();
+        let lines: Vec<Line> = help
+            .into_iter()
+            .map(|(keys, description)| {
+                Line::from(vec![
+                    format!("{keys:<keys_width$}  ").fg(self.theme.matched),
+                    description.into(),
+                ])
+            })
+            .collect();
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() + 4;
+        let height = lines.len() + 2;
+        let visible = usize::from(area.height.saturating_sub(2)).min(lines.len());
+        let scroll = scroll.min((lines.len() - visible) as u16);
+
+        let area = centered(
+            area,
+            (width as u16).min(area.width),
+            (height as u16).min(area.height),
+        );
+        let block = Block::bordered()
+            .title(Line::raw("Keys").centered())
+            .title_bottom(Line::raw("'?', <Esc> or 'q' to close, 'jk' to scroll").centered())
+            .border_style(self.title_bar())
+            .bg(self.theme.bg)
+            .padding(Padding::horizontal(1));
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .fg(self.theme.fg)
+            .scroll((scroll, 0))
+            .render(area, buf);
+    }
+
+    /// The counts shown in the summary, as pairs of what is counted and
+    /// the count, followed by the most common key prefixes.
+    pub(super) fn summary_rows(&self) -> Vec<(String, String)> {
+        let items = &self.env_list.items;
+        let paths = items
+            .iter()
+            .filter(|item| item.is_path_list() || looks_like_path(&item.value))
+            .count();
+        let secrets = items
+            .iter()
+            .filter(|item| self.masking.is_secret(&item.key))
+            .count();
+        let bytes: usize = items.iter().map(|item| item.value.len()).sum();
+        let mut rows = vec![
+            ("variables".to_string(), items.len().to_string()),
+            ("path-like".to_string(), paths.to_string()),
+            ("secrets".to_string(), secrets.to_string()),
+            ("bytes of values".to_string(), bytes.to_string()),
+        ];
+
+        let delimiter = self.env_list.delimiter;
+        let mut prefixes: Vec<(&str, usize)> = Vec::new();
+        for item in items.iter().filter(|item| item.key.contains(delimiter)) {
+            let prefix = group_prefix(&item.key, delimiter);
+            match prefixes.iter_mut().find(|(other, _)| *other == prefix) {
+                Some((_, count)) => *count += 1,
+                None => prefixes.push((prefix, 1)),
+            }
+        }
+        prefixes.retain(|&(_, count)| count > 1);
+        prefixes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        rows.extend(
+            prefixes
+                .into_iter()
+                .take(SUMMARY_PREFIXES)
+                .map(|(prefix, count)| (format!("{prefix}{delimiter}*"), count.to_string())),
+        );
+        rows
+    }
+
+    fn render_summary(&self, area: Rect, buf: &mut Buffer) {
+        let rows = self.summary_rows();
+        let label_width = rows
+            .iter()
+            .map(|(label, _)| label.width())
+            .max()
+            .unwrap_or_default();
+        let count_width = rows
+            .iter()
+            .map(|(_, count)| count.width())
+            .max()
+            .unwrap_or_default();
+        let height = rows.len() + 2;
+        let table = Table::new(
+            rows.into_iter().map(|(label, count)| {
+                TableRow::new([
+                    Cell::from(label),
+                    Cell::from(Line::raw(count).right_aligned().fg(self.theme.matched)),
+                ])
+            }),
+            [
+                Constraint::Length(label_width as u16),
+                Constraint::Length(count_width as u16),
+            ],
+        )
+        .column_spacing(2)
+        .block(
+            Block::bordered()
+                .title(Line::raw("Summary").centered())
+                .title_bottom(Line::raw("<Esc> to close").centered())
+                .border_style(self.title_bar())
+                .bg(self.theme.bg)
+                .padding(Padding::horizontal(1)),
+        )
+        .fg(self.theme.fg);
+
+        let width = (label_width + count_width + 6).max("<Esc> to close".len() + 4);
+        let area = centered(
+            area,
+            (width as u16).min(area.width),
+            (height as u16).min(area.height),
+        );
+        Clear.render(area, buf);
+        Widget::render(table, area, buf);
+    }
+
+    fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        let total = self.env_list.items.len();
+        let shown = self.env_list.view.len();
+        let filtered = !self.search.query.is_empty() && !self.search.jump
+            || !self.search.prefix.is_empty()
+            || self.bookmarks.only;
+        let count = if !filtered {
+            total.to_string()
+        } else {
+            format!("{shown}/{total}")
+        };
+        let [title_area, message_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(area);
+        Paragraph::new(format!("Ratatui Environment Reader ({count})"))
+            .bold()
+            .centered()
+            .render(title_area, buf);
+        self.render_message(message_area, buf);
+    }
+
+    /// Draw the feedback of the last action, if any.
+    fn render_message(&self, area: Rect, buf: &mut Buffer) {
+        let message = match &self.message {
+            Some(Message::Info(text)) => text.as_str().fg(self.theme.fg),
+            Some(Message::Error(text)) => text.as_str().fg(self.theme.error),
+            None => return,
+        };
+        Paragraph::new(message).centered().render(area, buf);
+    }
+
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        let total = self.env_list.view.len();
+        let mut position = match self.env_list.state.selected() {
+            Some(i) => format!(" [{}/{total}]", (i + 1).min(total)),
+            None => format!(" [-/{total}]"),
+        };
+        if let Some(count) = self.count {
+            position.insert_str(0, &format!(" {count}"));
+        }
+        if let Some(operator) = self.operator {
+            position.insert_str(0, &format!(" {}", operator.key()));
+        }
+        let [area, position_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(position.len() as u16),
+        ])
+        .areas(area);
+        Paragraph::new(position).render(position_area, buf);
+
+        if let Some(prompt) = &self.prompt {
+            let label = prompt.kind.label();
+            let width = usize::from(area.width).saturating_sub(label.width());
+            let mut line = prompt.input.line(width, Style::new().reversed());
+            line.spans.insert(0, label.bold());
+            Paragraph::new(line).render(area, buf);
+            return;
+        }
+
+        if self.search.editing {
+            let hint = match &self.search.error {
+                Some(error) => format!("  {error}").fg(self.theme.error),
+                None => "  (<Tab> key/value, Ctrl-r fuzzy/regex, Ctrl-t case, Ctrl-f jump)".into(),
+            };
+            Paragraph::new(Line::from(vec![
+                format!("[{}] /{}", self.search.label(), self.search.query).into(),
+                hint,
+            ]))
+            .render(area, buf);
+            return;
+        }
+        if !self.search.query.is_empty() {
+            let keys = if self.search.jump {
+                format!("{} matches, 'n'/'N' to jump, ", self.env_list.hits.len())
+            } else {
+                String::new()
+            };
+            Paragraph::new(format!("{keys}<Esc> to clear the search, '/' to edit it"))
+                .centered()
+                .render(area, buf);
+            return;
+        }
+        let mut spans = Vec::new();
+        if self.env_list.skipped > 0 {
+            spans.push(
+                format!("[{} malformed lines skipped] ", self.env_list.skipped)
+                    .fg(self.theme.error),
+            );
+        }
+        match self.env_list.selected() {
+            // without the value pane, the footer is the only place left for it.
+            Some(item) if !self.value_pane => {
+                let value = if self.masking.is_masked(item) {
+                    Cow::Borrowed(MASK)
+                } else {
+                    Cow::Owned(sanitize(&item.value).replace('\n', "↵"))
+                };
+                spans.push(format!("{}=", sanitize(&item.key)).bold());
+                spans.push(value.into_owned().into());
+            }
+            _ => {
+                // copying takes the key twice, like yy.
+                let copy = match self.keymap.keys(Action::Copy).next() {
+                    Some(key) if key.to_string().chars().count() == 1 => format!("'{key}{key}'"),
+                    Some(key) => format!("'{key}' twice"),
+                    None => "'yy'".to_string(),
+                };
+                spans.push(
+                    format!(
+                        "Use ↓↑ or 'jk' to move, '/' to search, {copy} to copy, '?' for help and 'q' to quit"
+                    )
+                    .into(),
+                );
+            }
+        }
+        Paragraph::new(Line::from(spans))
+            .centered()
+            .render(area, buf);
+    }
+
+    /// Tags for the modes that shape the list, like `[sort: key ↑]` or
+    /// `[/query ...]`.
+    pub(super) fn mode_tags(&self) -> Vec<String> {
+        let mut tags = vec![format!("[{}]", self.env_list.sort_label())];
+        if !self.search.prefix.is_empty() {
+            tags.push(format!("[prefix {}]", self.search.prefix));
+        }
+        if !self.search.query.is_empty() && !self.search.editing {
+            tags.push(format!("[/{} {}]", self.search.query, self.search.label()));
+        }
+        if self.bookmarks.only {
+            tags.push("[bookmarks only]".to_string());
+        }
+        if self.env_list.grouped {
+            tags.push(format!("[grouped by {}]", self.env_list.delimiter));
+        }
+        if let Some(baseline) = &self.env_list.baseline {
+            tags.push(format!("[baseline {}]", baseline.origin));
+        }
+        if !self.selection.is_empty() {
+            tags.push(format!("[{} selected]", self.selection.len()));
+        }
+        if self.masking.enabled {
+            tags.push("[mask]".to_string());
+        }
+        if let Some(key) = &self.pinned {
+            tags.push(format!("[pin {}]", sanitize(key)));
+        }
+        if self.watch {
+            tags.push("[watch]".to_string());
+        }
+        tags
+    }
+
+    /// Draw the active modes as a line of tags.
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(self.mode_tags().join(" "))
+            .fg(self.theme.muted)
+            .centered()
+            .render(area, buf);
+    }
+
+    /// The style of a pane title, bright when the pane has focus.
+    fn title_style(&self, pane: Focus) -> Style {
+        if self.focus == pane {
+            self.title_bar().bold()
+        } else {
+            Style::new()
+                .fg(self.theme.muted)
+                .bg(self.theme.selection_bg)
+        }
+    }
+
+    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
+        self.list_area = area;
+        let block = Block::new()
+            .title(
+                Line::raw(if self.list_values {
+                    "Environment List (values)"
+                } else {
+                    "Environment List"
+                })
+                .centered(),
+            )
+            .borders(Borders::TOP)
+            .border_set(symbols::border::EMPTY)
+            .border_style(self.title_style(Focus::List))
+            .bg(self.theme.bg);
+
+        let rows = self.env_list.rows();
+        let inner = block.inner(area);
+        let height = usize::from(inner.height);
+
+        // the list state selects view positions, the drawn list selects rows.
+        let selected = self.env_list.state.selected().and_then(|selected| {
+            rows.iter()
+                .position(|row| matches!(*row, Row::Item { position, .. } if position == selected))
+        });
+        // the highlight symbol takes the first columns and the scrollbar the last.
+        let scrollbar = rows.len() > usize::from(area.height.saturating_sub(1));
+        let width = usize::from(area.width)
+            .saturating_sub(self.highlight.width(selected.is_some()) + usize::from(scrollbar));
+        let offset = list_offset(self.env_list.state.offset(), selected, height, rows.len());
+        let shown = offset..(offset + height).min(rows.len());
+
+        // building the items is the slowest part of a frame with thousands
+        // of variables, so only the rows around the shown ones are built,
+        // and only when they look different.
+        let flashing = !self.env_list.flashes.is_empty();
+        let built = self.list_rows.start <= shown.start && shown.end <= self.list_rows.end;
+        if self.env_list.dirty || flashing || width != self.list_width || !built {
+            let start = offset.saturating_sub(LIST_BUFFER);
+            let end = (shown.end + LIST_BUFFER).min(rows.len());
+            if self.columns {
+                self.table = self.build_table(&rows[start..end], width);
+            } else {
+                self.list = self.build_list(&rows[start..end], width);
+            }
+            self.list_rows = start..end;
+            self.list_width = width;
+            self.env_list.dirty = false;
+        }
+
+        let start = self.list_rows.start;
+        let offset_in_built = offset - start;
+        let selected_in_built = selected.map(|selected| selected - start);
+        block.render(area, buf);
+        // the cached widgets outlive the borrow of the highlight symbol, so
+        // it goes on a copy of them, which only holds the rows built above.
+        let symbol = self.highlight.symbol.as_str();
+        if self.columns {
+            let mut state = TableState::default()
+                .with_offset(offset_in_built)
+                .with_selected(selected_in_built);
+            let table: Table = self.table.clone();
+            StatefulWidget::render(table.highlight_symbol(symbol), inner, buf, &mut state);
+        } else {
+            let mut state = ListState::default()
+                .with_offset(offset_in_built)
+                .with_selected(selected_in_built);
+            let list: List = self.list.clone();
+            StatefulWidget::render(list.highlight_symbol(symbol), inner, buf, &mut state);
+        }
+        *self.env_list.state.offset_mut() = offset;
+        self.render_list_scrollbar(area, buf, rows.len());
+
+        if rows.is_empty() {
+            let message = self.empty_message();
+            Paragraph::new(message.fg(self.theme.muted))
+                .centered()
+                .render(centered(inner, inner.width, 1), buf);
+        }
+    }
+
+    /// The spans drawn before the key of the variable at view `position`.
+    fn key_prefix(&self, position: usize, grouped: bool) -> Vec<Span<'static>> {
+        let item = &self.env_list.items[self.env_list.view[position]];
+        let width = self.env_list.view.len().to_string().len();
+        let mut prefix: Vec<Span> = Vec::new();
+        if self.line_numbers {
+            prefix.push(format!("{:0width$} ", position + 1).fg(self.theme.muted));
+        }
+        if grouped {
+            prefix.push(Span::raw("  "));
+        }
+        if !self.bookmarks.keys.is_empty() {
+            let marker = if self.bookmarks.contains(item) {
+                "★ "
+            } else {
+                "  "
+            };
+            prefix.push(marker.fg(self.theme.matched));
+        }
+        if !self.selection.is_empty() {
+            let marker = if self.selection.contains(&item.key) {
+                "✓ "
+            } else {
+                "  "
+            };
+            prefix.push(marker.fg(self.theme.added).bold());
+        }
+        if let Some(status) = &item.status {
+            prefix.push(Span::raw(status.marker()));
+        }
+        if !item.overridden.is_empty() {
+            prefix.push("! ".fg(self.theme.error).bold());
+        }
+        if item.value.contains('\n') && !self.masking.is_masked(item) {
+            prefix.push("¶ ".fg(self.theme.muted));
+        }
+        prefix
+    }
+
+    /// The key drawn for `row`, cut to `key_width` columns, and its style.
+    fn row_key(&self, row: &Row, key_width: usize) -> (Text<'static>, Style) {
+        let (n, grouped) = match *row {
+            Row::Group { ref prefix, len } => {
+                let header = format!("{prefix}{}* ({len})", self.env_list.delimiter);
+                return (Text::raw(header), Style::new().fg(self.theme.muted).bold());
+            }
+            Row::Item { position, grouped } => (position, grouped),
+        };
+        let item = &self.env_list.items[self.env_list.view[n]];
+        let prefix = self.key_prefix(n, grouped);
+        let used: usize = prefix.iter().map(Span::width).sum();
+        let width = key_width.saturating_sub(used);
+        let (key, matches) = if self.list_values {
+            self.list_value(item, width)
+        } else {
+            let key = truncate(&item.key, width);
+            let mut matches = self.search.key_matches(item);
+            if let Cow::Owned(cut) = &key {
+                // matches past the cut would light up the ellipsis.
+                let kept = cut.chars().count() - 1;
+                matches.retain(|&i| i < kept);
+            }
+            (key, matches)
+        };
+        let matched = Style::new().fg(self.theme.matched).bold();
+        let mut text = highlight(&key, &matches, matched);
+        text.lines[0].spans.splice(0..0, prefix);
+        let style = match (&item.status, self.env_list.flash(item)) {
+            // fade from reversed to just colored over the flash.
+            (_, Some(elapsed)) if elapsed < FLASH_DURATION / 2 => {
+                Style::new().fg(self.theme.changed).reversed()
+            }
+            (_, Some(_)) => Style::new().fg(self.theme.changed),
+            (Some(status), None) => Style::new().fg(status.color(&self.theme)),
+            // the matches stand out when jumping between them.
+            (None, None) if !self.env_list.hits.is_empty() && !self.env_list.is_hit(n) => {
+                Style::new().fg(self.theme.muted)
+            }
+            (None, None) => Style::new(),
+        };
+        let style = if item.exported { style } else { style.italic() };
+        (text, style)
+    }
+
+    /// The value of `item` as drawn in the list in place of its key, cut to
+    /// `width` columns, with the matched chars.
+    fn list_value(&self, item: &Environment, width: usize) -> (Cow<'static, str>, Vec<usize>) {
+        if self.masking.is_masked(item) {
+            return (Cow::Borrowed(MASK), Vec::new());
+        }
+        let (value, mut matches) = match sanitize(&item.value) {
+            // a line break takes one char as an arrow, so the matches stay.
+            Cow::Borrowed(value) => (value.replace('\n', "↵"), self.search.value_matches(item)),
+            // spelling out control chars moves the matches.
+            Cow::Owned(value) => (value.replace('\n', "↵"), Vec::new()),
+        };
+        let value = match truncate(&value, width) {
+            Cow::Owned(cut) => {
+                // matches past the cut would light up the ellipsis.
+                let kept = cut.chars().count() - 1;
+                matches.retain(|&i| i < kept);
+                cut
+            }
+            Cow::Borrowed(_) => value,
+        };
+        (Cow::Owned(value), matches)
+    }
+
+    fn selection_style(&self) -> Style {
+        let style = Style::new()
+            .bg(self.theme.selection_bg)
+            .add_modifier(Modifier::BOLD);
+        if self.color {
+            style
+        } else {
+            style.add_modifier(Modifier::REVERSED)
+        }
+    }
+
+    /// The style of titles and overlay borders, reversed without colors.
+    fn title_bar(&self) -> Style {
+        let style = Style::new().fg(self.theme.title_fg).bg(self.theme.title_bg);
+        if self.color {
+            style
+        } else {
+            style.add_modifier(Modifier::REVERSED)
+        }
+    }
+
+    /// Build the list items for `rows`, with keys cut to `key_width` columns.
+    fn build_list(&self, rows: &[Row], key_width: usize) -> List<'static> {
+        let items: Vec<ListItem> = rows
+            .iter()
+            .map(|row| {
+                let (text, style) = self.row_key(row, key_width);
+                ListItem::new(text).style(style)
+            })
+            .collect();
+
+        List::new(items)
+            .highlight_style(self.selection_style())
+            .highlight_spacing(self.highlight.spacing.clone())
+    }
+
+    /// Build the table rows for `rows`, splitting `width` columns between the
+    /// keys and the first line of their values.
+    fn build_table(&self, rows: &[Row], width: usize) -> Table<'static> {
+        let key_width = width * 2 / 5;
+        // one column separates the key from the value.
+        let value_width = width.saturating_sub(key_width + 1);
+        let table_rows: Vec<TableRow> = rows
+            .iter()
+            .map(|row| {
+                let (key, style) = self.row_key(row, key_width);
+                let value = match *row {
+                    Row::Group { .. } => Text::default(),
+                    Row::Item { position, .. } => {
+                        let item = &self.env_list.items[self.env_list.view[position]];
+                        if self.list_values {
+                            Text::raw(truncate(&item.key, value_width).into_owned())
+                        } else if self.masking.is_masked(item) {
+                            Text::raw(MASK).fg(self.theme.muted)
+                        } else {
+                            // line breaks would take more than the row.
+                            let value = sanitize(&item.value).replace('\n', "↵");
+                            Text::raw(truncate(&value, value_width).into_owned())
+                        }
+                    }
+                };
+                TableRow::new([Cell::from(key), Cell::from(value)]).style(style)
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(u16::try_from(key_width).unwrap_or(u16::MAX)),
+            Constraint::Fill(1),
+        ];
+        Table::new(table_rows, widths)
+            .column_spacing(1)
+            .row_highlight_style(self.selection_style())
+            .highlight_spacing(self.highlight.spacing.clone())
+    }
+
+    /// Why the list has nothing to show.
+    fn empty_message(&self) -> String {
+        if self.env_list.items.is_empty() {
+            "No variables".to_string()
+        } else if !self.search.query.is_empty() {
+            format!("No variables match '{}'", self.search.query)
+        } else if !self.search.prefix.is_empty() {
+            format!("No variables start with '{}'", self.search.prefix)
+        } else if self.bookmarks.only {
+            "No bookmarked variables, press 'b' to bookmark one".to_string()
+        } else {
+            "No variables shown".to_string()
+        }
+    }
+
+    fn render_list_scrollbar(&mut self, area: Rect, buf: &mut Buffer, len: usize) {
+        // skip the title row of the list block.
+        let area = Rect {
+            y: area.y + 1,
+            height: area.height.saturating_sub(1),
+            ..area
+        };
+        let viewport = usize::from(area.height);
+        if len <= viewport || area.is_empty() {
+            return;
+        }
+
+        // positions go up to the offset that shows the last item at the bottom.
+        self.env_list.scrollbar = self
+            .env_list
+            .scrollbar
+            .content_length(len - viewport + 1)
+            .viewport_content_length(viewport)
+            .position(self.env_list.state.offset());
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(Style::new().fg(self.theme.scrollbar_track))
+            .thumb_style(Style::new().fg(self.theme.scrollbar_thumb));
+        StatefulWidget::render(scrollbar, area, buf, &mut self.env_list.scrollbar);
+    }
+
+    fn render_selected_item(&mut self, area: Rect, buf: &mut Buffer) {
+        self.value_area = area;
+        // the description of a well-known variable takes the row under the title.
+        let description = self
+            .value_item()
+            .and_then(|item| self.descriptions.get(&item.key))
+            .map(str::to_string);
+        let note = u16::from(description.is_some());
+        // the block takes the top row for its title.
+        self.env_list.value_height = area.height.saturating_sub(1 + note);
+        let height = usize::from(self.env_list.value_height);
+        let scroll = usize::from(self.env_list.value_scroll);
+        let referrers = self.referrer_lines();
+
+        let title = self.value_title();
+        let width = usize::from(area.width);
+        let block = |position: Option<String>| {
+            // the position takes the right end of the title row.
+            let reserved = position.as_ref().map_or(0, |position| position.width() + 1);
+            // a title too long for the pane reads from its start, where the key is.
+            let title = if title.width() + 2 * reserved <= width {
+                Line::raw(title.as_str()).centered()
+            } else if reserved == 0 {
+                Line::raw(title.as_str()).left_aligned()
+            } else {
+                Line::raw(truncate(&title, width.saturating_sub(reserved))).left_aligned()
+            };
+            let block = Block::new()
+                .title(title)
+                .borders(Borders::TOP)
+                .border_set(symbols::border::EMPTY)
+                .border_style(self.title_style(Focus::Value))
+                .bg(self.theme.bg)
+                .padding(Padding::new(1, 1, note, 0));
+            match position {
+                Some(position) => block.title(Line::raw(position).right_aligned()),
+                None => block,
+            }
+        };
+
+        if let Some(item) = self.value_item()
+            && item.is_path_list()
+            && !matches!(item.status, Some(DiffStatus::Changed { .. }))
+            && !self.masking.is_masked(item)
+        {
+            let paths: Vec<_> = env::split_paths(&item.value).collect();
+            let repeated = repeated_paths(&paths);
+            let width = paths.len().to_string().len();
+            let mut items: Vec<ListItem> = paths
+                .iter()
+                .zip(repeated)
+                .enumerate()
+                .filter(|(_, (_, earlier))| !self.unique_paths || earlier.is_none())
+                .map(|(i, (path, earlier))| {
+                    let path = path.display().to_string();
+                    let references = self.cross_references(item, &path);
+                    let mut text = highlight(&path, &references, Style::new().underlined());
+                    let number = format!("{:>width$} ", i + 1).fg(self.theme.muted);
+                    text.lines[0].spans.insert(0, number);
+                    if let Some(earlier) = earlier {
+                        // the repeat has no effect, the first one wins.
+                        for span in &mut text.lines[0].spans[1..] {
+                            span.style = span.style.fg(self.theme.changed).crossed_out();
+                        }
+                        let note = format!("  same as {}", earlier + 1);
+                        text.lines[0].spans.push(note.fg(self.theme.muted));
+                    }
+                    if self.stat_paths {
+                        let marker = match PathKind::of(Path::new(&path)) {
+                            PathKind::File => "f ".fg(self.theme.muted),
+                            PathKind::Directory => "d ".fg(self.theme.muted),
+                            PathKind::Missing => "✗ ".fg(self.theme.error),
+                        };
+                        text.lines[0].spans.insert(1, marker);
+                    }
+                    ListItem::new(text)
+                })
+                .collect();
+            items.extend(referrers.into_iter().map(ListItem::new));
+            let rows = items.len();
+            let scroll = scroll.min(rows.saturating_sub(height));
+            let block = block(scroll_position(scroll, height, rows));
+            let items: Vec<ListItem> = items.into_iter().skip(scroll).collect();
+            Widget::render(List::new(items).block(block).fg(self.theme.fg), area, buf);
+            self.render_description(description.as_deref(), area, buf);
+            self.env_list.value_scroll = scroll as u16;
+            return;
+        }
+
+        let mut info = match self.value_item() {
+            Some(item) => self.value_text(item),
+            None => Text::raw("Nothing selected"),
+        };
+        info.extend(referrers);
+
+        let rows = if self.wrap {
+            // the padding takes a column on each side.
+            wrapped_rows(&info, area.width.saturating_sub(2))
+        } else {
+            info.lines.len()
+        };
+        let scroll = scroll.min(rows.saturating_sub(height));
+        let block = block(scroll_position(scroll, height, rows));
+        let paragraph = Paragraph::new(info).block(block).fg(self.theme.fg);
+        if self.wrap {
+            paragraph
+                .wrap(Wrap { trim: false })
+                .scroll((scroll as u16, 0))
+                .render(area, buf);
+        } else {
+            paragraph
+                .scroll((scroll as u16, self.env_list.value_hscroll))
+                .render(area, buf);
+        }
+        self.render_description(description.as_deref(), area, buf);
+        self.env_list.value_scroll = scroll as u16;
+    }
+
+    /// Draw `description` in the row under the title of the value pane.
+    fn render_description(&self, description: Option<&str>, area: Rect, buf: &mut Buffer) {
+        let Some(description) = description else {
+            return;
+        };
+        if area.height < 2 {
+            return;
+        }
+        let row = Rect::new(area.x + 1, area.y + 1, area.width.saturating_sub(2), 1);
+        Line::from(description.fg(self.theme.muted).italic()).render(row, buf);
+    }
+
+    fn value_title(&self) -> String {
+        let Some(item) = self.value_item() else {
+            return "Value".to_string();
+        };
+        let name = sanitize(&item.key).into_owned();
+        let name = if self.pinned.is_some() {
+            format!("[pinned] {name}")
+        } else {
+            name
+        };
+        let mut title = if self.masking.is_masked(item) {
+            name
+        } else {
+            let lines = match item.value.lines().count() {
+                0 | 1 => String::new(),
+                lines => format!(", {lines} lines"),
+            };
+            format!(
+                "{name} ({} bytes, {} chars{lines})",
+                item.value.len(),
+                item.value.chars().count()
+            )
+        };
+        if item.lossy {
+            title.push_str(" [invalid UTF-8]");
+        }
+        if !item.overridden.is_empty() {
+            let times = item.overridden.len() + 1;
+            title.push_str(&format!(" [set {times} times]"));
+        }
+        if !item.exported {
+            title.push_str(" [not exported]");
+        }
+        if self.masking.is_masked(item) {
+            return title;
+        }
+        if self.decoded {
+            title.push_str(" [base64-decoded]");
+        } else if looks_like_base64(&item.value) {
+            title.push_str(" [base64? 'B' to decode]");
+        }
+        if self.raw {
+            title.push_str(" [raw]");
+        }
+        if item.is_path_list() {
+            let paths: Vec<_> = env::split_paths(&item.value).collect();
+            let repeats = repeated_paths(&paths).iter().flatten().count();
+            match (repeats, self.unique_paths) {
+                (0, _) => {}
+                (_, true) => title.push_str(&format!(" [{repeats} repeated, hidden]")),
+                (_, false) => title.push_str(&format!(" [{repeats} repeated, 'U' to hide]")),
+            }
+        }
+        if self.stat_paths && looks_like_path(&item.value) && !item.is_path_list() {
+            let kind = PathKind::of(Path::new(&item.value));
+            title.push_str(&format!(" [{}]", kind.label()));
+        }
+        title
+    }
+
+    /// The contents of the value pane for `item`, borrowing its value where
+    /// it is drawn as is.
+    fn value_text<'a>(&self, item: &'a Environment) -> Text<'a> {
+        let masked = self.masking.is_masked(item);
+        let value = if masked {
+            Text::raw(MASK)
+        } else if self.decoded
+            && let Some(bytes) = decode_base64(&item.value)
+        {
+            match String::from_utf8(bytes) {
+                Ok(text) if self.raw => Text::raw(text),
+                Ok(text) => Text::raw(sanitize(&text).into_owned()),
+                Err(err) => Text::raw(hex_dump(err.as_bytes())),
+            }
+        } else if self.raw {
+            self.mark_line_ends(Text::raw(item.value.as_str()))
+        } else if self.pretty_json
+            && let Some(json) = pretty_json(&item.value)
+        {
+            self.json_text(&json)
+        } else {
+            let matched = Style::new().fg(self.theme.matched).bold();
+            let found = self.search.value_matches(item);
+            let references = self.cross_references(item, &item.value);
+            let text = style_chars(&item.value, |i| {
+                let style = if found.contains(&i) {
+                    matched
+                } else {
+                    Style::new()
+                };
+                if references.contains(&i) {
+                    style.underlined()
+                } else {
+                    style
+                }
+            });
+            self.mark_line_ends(text)
+        };
+        let value = if self.expand && !masked {
+            let mut value = value;
+            value.extend(self.expanded_text(item));
+            value
+        } else {
+            value
+        };
+        let value = if item.overridden.is_empty() {
+            value
+        } else {
+            let mut value = value;
+            value.extend([
+                Line::default(),
+                Line::from("overrides the earlier values:".fg(self.theme.error)),
+            ]);
+            for earlier in &item.overridden {
+                let earlier = if masked { MASK } else { earlier };
+                value.extend(Text::raw(sanitize(earlier)));
+            }
+            value
+        };
+        match &item.status {
+            Some(DiffStatus::Changed { file_value }) => {
+                let file_value = if masked { MASK } else { file_value };
+                let (before, now) = match self.env_list.baseline {
+                    Some(_) => ("baseline:", "now:"),
+                    None => ("file:", "live:"),
+                };
+                let mut text = Text::from(vec![Line::from(before.fg(self.theme.removed))]);
+                text.extend(Text::raw(sanitize(file_value)));
+                text.extend([Line::default(), Line::from(now.fg(self.theme.added))]);
+                text.extend(value);
+                text
+            }
+            _ => value,
+        }
+    }
+
+    /// End every line of `text` but the last with a `¶`, so line breaks in
+    /// the value stand out from wrapping.
+    fn mark_line_ends<'a>(&self, mut text: Text<'a>) -> Text<'a> {
+        let last = text.lines.len().saturating_sub(1);
+        for line in &mut text.lines[..last] {
+            line.spans.push("¶".fg(self.theme.muted));
+        }
+        text
+    }
+
+    /// The value of `item` with its references resolved, or nothing if it
+    /// has none.
+    ///
+    /// References resolve to the listed variables first and then to the
+    /// process environment, and masked variables stay masked. Values that
+    /// refer to other variables are expanded in turn, up to a few levels.
+    fn expanded_text(&self, item: &Environment) -> Text<'static> {
+        let lookup = |name: &str| match self.env_list.items.iter().find(|other| other.key == name) {
+            Some(other) if self.masking.is_masked(other) => Some(MASK.to_string()),
+            Some(other) => Some(other.value.clone()),
+            None => env::var(name).ok(),
+        };
+        let (mut resolved, mut unresolved) = expand_references(&item.value, lookup);
+        for _ in 1..MAX_EXPANSION_DEPTH {
+            let (next, next_unresolved) = expand_references(&resolved, lookup);
+            if next == resolved {
+                break;
+            }
+            (resolved, unresolved) = (next, next_unresolved);
+        }
+        if resolved == item.value && unresolved.is_empty() {
+            return Text::default();
+        }
+        let mut text = Text::from(vec![
+            Line::default(),
+            Line::from("resolved:".fg(self.theme.muted)),
+        ]);
+        text.extend(Text::raw(sanitize(&resolved).into_owned()));
+        if !unresolved.is_empty() {
+            let names: Vec<String> = unresolved.iter().map(|name| format!("${name}")).collect();
+            let line = format!("unresolved: {}", names.join(", "));
+            text.push_line(line.fg(self.theme.error));
+        }
+        text
+    }
+
+    /// The char positions of `text` that spell out the whole value of a
+    /// variable other than `item`, when cross-references are shown.
+    fn cross_references(&self, item: &Environment, text: &str) -> Vec<usize> {
+        if !self.cross_references {
+            return Vec::new();
+        }
+        let mut covered = vec![false; text.len()];
+        for other in &self.env_list.items {
+            if other.key == item.key
+                || other.value.chars().count() < MIN_REFERENCE_LEN
+                || self.is_redacted(other)
+            {
+                continue;
+            }
+            for (start, found) in text.match_indices(other.value.as_str()) {
+                covered[start..start + found.len()].fill(true);
+            }
+        }
+        text.char_indices()
+            .enumerate()
+            .filter(|&(_, (byte, _))| covered[byte])
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The variables other than the shown one whose values contain its
+    /// value, searched for again only once the shown variable or its value
+    /// changes. Masked secrets are left out on either side.
+    fn find_referrers(&mut self) -> Option<&[String]> {
+        let item = self.value_item()?;
+        let cached = self
+            .referrers
+            .as_ref()
+            .is_some_and(|referrers| referrers.key == item.key && referrers.value == item.value);
+        if !cached {
+            let keys = self
+                .env_list
+                .items
+                .iter()
+                .filter(|other| {
+                    other.key != item.key
+                        && !self.is_redacted(item)
+                        && !self.is_redacted(other)
+                        && other.value.contains(&item.value)
+                })
+                .map(|other| other.key.clone())
+                .collect();
+            self.referrers = Some(Referrers {
+                key: item.key.clone(),
+                value: item.value.clone(),
+                keys,
+            });
+        }
+        self.referrers
+            .as_ref()
+            .map(|referrers| referrers.keys.as_slice())
+    }
+
+    /// The lines listing the referrers of the shown value, when asked for.
+    fn referrer_lines(&mut self) -> Vec<Line<'static>> {
+        if !self.show_referrers {
+            return Vec::new();
+        }
+        let short = self
+            .value_item()
+            .is_some_and(|item| item.value.chars().count() < MIN_REFERENCE_LEN);
+        let muted = self.theme.muted;
+        let heading = |text: String| Line::from(text.fg(muted));
+        if short {
+            let text = "too short to look for in other values".to_string();
+            return vec![Line::default(), heading(text)];
+        }
+        let Some(keys) = self.find_referrers() else {
+            return Vec::new();
+        };
+        let text = match keys.len() {
+            0 => "not found in other values".to_string(),
+            1 => "found in the value of 1 variable:".to_string(),
+            n => format!("found in the values of {n} variables:"),
+        };
+        let keys: Vec<String> = keys.iter().map(|key| sanitize(key).into_owned()).collect();
+        let mut lines = vec![Line::default(), heading(text)];
+        lines.extend(keys.into_iter().map(|key| Line::raw(format!("  {key}"))));
+        lines
+    }
+
+    /// Color the keys of pretty-printed JSON.
+    fn json_text(&self, json: &str) -> Text<'static> {
+        json.lines()
+            .map(|line| {
+                let indent = line.len() - line.trim_start().len();
+                match line[indent..].split_once("\": ") {
+                    Some((key, rest)) if key.starts_with('"') => Line::from(vec![
+                        Span::raw(line[..indent].to_string()),
+                        format!("{key}\"").fg(self.theme.json_key),
+                        Span::raw(format!(": {rest}")),
+                    ]),
+                    _ => Line::raw(line.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{app_with, press, selected_key};
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{Terminal, backend::TestBackend, style::Color};
+
+    /// Draw `app` and return the rows of the screen, skipping the cells
+    /// covered by wide chars.
+    fn draw(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(&mut *app, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                let mut row = String::new();
+                let mut x = 0;
+                while x < width {
+                    let symbol = buffer[(x, y)].symbol();
+                    row.push_str(symbol);
+                    x += symbol.width().max(1) as u16;
+                }
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sanitize_spells_out_control_chars() {
+        assert_eq!(sanitize("plain\nlines"), "plain\nlines");
+        assert_eq!(sanitize("\x1b[1m\tbold\x7f"), "^[[1m^Ibold^?");
+        assert_eq!(sanitize("\u{85}"), "<U+0085>");
+    }
+
+    #[test]
+    fn truncate_counts_columns() {
+        assert_eq!(truncate("日本語", 6), "日本語");
+        assert_eq!(truncate("日本語", 5), "日本…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("KEY", 0), "");
+    }
+
+    #[test]
+    fn wide_chars_keep_the_layout() {
+        let mut app = app_with(vec![
+            Environment::new("環境変数_とても長いキーの名前".into(), "値 🎉 です".into()),
+            Environment::new("PLAIN".into(), "ascii".into()),
+        ]);
+        app.env_list.state.select(Some(0));
+        let rows = draw(&mut app, 20, 10);
+
+        for row in &rows {
+            assert_eq!(row.width(), 20, "{row:?} does not fill the screen");
+        }
+        let key_row = rows.iter().find(|row| row.contains('環')).unwrap();
+        assert!(key_row.trim_end().ends_with('…'), "{key_row:?}");
+        assert!(
+            rows.iter().any(|row| row.contains("値 🎉 です")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn long_lists_scroll_to_any_selection() {
+        let items = (0..500)
+            .map(|i| Environment::new(format!("VAR_{i:03}"), String::new()))
+            .collect();
+        let mut app = app_with(items);
+        let shows = |rows: &[String], key: &str| rows.iter().any(|row| row.contains(key));
+
+        for (selected, hidden) in [(499, "VAR_000"), (250, "VAR_499"), (0, "VAR_250")] {
+            app.env_list.state.select(Some(selected));
+            let rows = draw(&mut app, 40, 20);
+            assert!(shows(&rows, &format!(">VAR_{selected:03}")), "{rows:#?}");
+            assert!(!shows(&rows, hidden), "{rows:#?}");
+        }
+    }
+
+    #[test]
+    fn cut_keys_are_shown_in_full_in_the_value_title() {
+        let key = "A_VARIABLE_WITH_A_VERY_LONG_NAME";
+        let mut app = app_with(vec![Environment::new(key.into(), "value".into())]);
+        app.env_list.state.select(Some(0));
+        let rows = draw(&mut app, 32, 10);
+        assert!(rows.iter().any(|row| row.ends_with("NA…")), "{rows:#?}");
+        assert!(rows.iter().any(|row| row.starts_with(key)), "{rows:#?}");
+    }
+
+    #[test]
+    fn column_mode_shows_values_next_to_keys() {
+        let mut app = app_with(vec![
+            Environment::new("EDITOR".into(), "vim".into()),
+            Environment::new("LINES".into(), "one\ntwo".into()),
+        ]);
+        press(&mut app, "cjj");
+        assert_eq!(selected_key(&app), Some("LINES"));
+        let rows = draw(&mut app, 40, 10);
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("EDITOR") && row.contains("vim")),
+            "{rows:#?}"
+        );
+        assert!(
+            rows.iter()
+                .any(|row| row.starts_with(">¶ LINES") && row.contains("one↵two")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn line_breaks_in_values_are_marked() {
+        let mut app = app_with(vec![
+            Environment::new("ONE".into(), "single".into()),
+            Environment::new("CERT".into(), "first\nsecond".into()),
+        ]);
+        press(&mut app, "jj");
+        let rows = draw(&mut app, 40, 12);
+        assert!(
+            rows.iter().any(|row| row.starts_with(">¶ CERT")),
+            "{rows:#?}"
+        );
+        assert!(rows.iter().any(|row| row.starts_with(" ONE")), "{rows:#?}");
+        assert!(
+            rows.iter().any(|row| row.contains(", 2 lines)")),
+            "{rows:#?}"
+        );
+        assert!(
+            rows.iter().any(|row| row.trim_end().ends_with("first¶")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn paths_are_checked_on_disk_when_enabled() {
+        let dir = env::temp_dir().display().to_string();
+        let mut app = app_with(vec![
+            Environment::new("DIR".into(), dir.clone()),
+            Environment::new("DIRS".into(), format!("{dir}:/envirust/missing")),
+        ]);
+        press(&mut app, "j");
+        let rows = draw(&mut app, 60, 12);
+        assert!(
+            !rows.iter().any(|row| row.contains("[directory]")),
+            "{rows:#?}"
+        );
+        press(&mut app, "F");
+        let rows = draw(&mut app, 60, 12);
+        assert!(
+            rows.iter().any(|row| row.contains("[directory]")),
+            "{rows:#?}"
+        );
+        press(&mut app, "j");
+        let rows = draw(&mut app, 60, 12);
+        assert!(
+            rows.iter().any(|row| row.contains("2 ✗ /envirust/missing")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn summary_counts_kinds_and_prefixes() {
+        let mut app = app_with(vec![
+            Environment::new("XDG_DATA_HOME".into(), "/data".into()),
+            Environment::new("XDG_CONFIG_HOME".into(), "/config".into()),
+            Environment::new("GITHUB_TOKEN".into(), "abc".into()),
+            Environment::new("LANG".into(), "C".into()),
+        ]);
+        let rows = app.summary_rows();
+        let count = |label: &str| {
+            rows.iter()
+                .find(|(row, _)| row == label)
+                .map(|(_, count)| count.as_str())
+        };
+        assert_eq!(count("variables"), Some("4"));
+        assert_eq!(count("path-like"), Some("2"));
+        assert_eq!(count("secrets"), Some("1"));
+        assert_eq!(count("bytes of values"), Some("16"));
+        assert_eq!(count("XDG_*"), Some("2"));
+        assert_eq!(count("GITHUB_*"), None);
+
+        press(&mut app, "i");
+        let rows = draw(&mut app, 60, 16);
+        assert!(rows.iter().any(|row| row.contains("Summary")), "{rows:#?}");
+        app.on_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.overlay, None);
+    }
+
+    #[test]
+    fn monochrome_emphasizes_with_reversed_text() {
+        let mut app = app_with(vec![
+            Environment::new("FIRST".into(), "1".into()),
+            Environment::new("SECOND".into(), "2".into()),
+        ])
+        .without_color();
+        press(&mut app, "j");
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(&mut app, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let cells: Vec<_> = (0..12)
+            .flat_map(|y| (0..40).map(move |x| (x, y)))
+            .map(|position| &buffer[position])
+            .collect();
+        assert!(
+            cells
+                .iter()
+                .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset)
+        );
+        let selected = cells
+            .iter()
+            .find(|cell| cell.symbol() == "F")
+            .expect("the selected key is drawn");
+        assert!(selected.modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn small_terminals_ask_for_more_room() {
+        let mut app = app_with(vec![Environment::new("HOME".into(), "/home/user".into())]);
+        let rows = draw(&mut app, 40, 9);
+        assert!(
+            rows.iter().any(|row| row.contains("Terminal too small")),
+            "{rows:#?}"
+        );
+        let rows = draw(&mut app, 40, 10);
+        assert!(rows.iter().any(|row| row.contains("HOME")), "{rows:#?}");
+
+        press(&mut app, "V");
+        let rows = draw(&mut app, 40, 7);
+        assert!(rows.iter().any(|row| row.contains("HOME")), "{rows:#?}");
+    }
+
+    #[test]
+    fn repeated_paths_are_marked_or_hidden() {
+        let mut app = app_with(vec![Environment::new(
+            "PATH".into(),
+            "/usr/bin:/bin:/usr/bin".into(),
+        )]);
+        press(&mut app, "j");
+        let rows = draw(&mut app, 60, 13);
+        assert!(
+            rows.iter().any(|row| row.contains("3 /usr/bin  same as 1")),
+            "{rows:#?}"
+        );
+        assert!(
+            rows.iter().any(|row| row.contains("[1 repeated")),
+            "{rows:#?}"
+        );
+
+        press(&mut app, "U");
+        let rows = draw(&mut app, 60, 13);
+        assert!(rows.iter().any(|row| row.contains("2 /bin")), "{rows:#?}");
+        assert!(
+            !rows.iter().any(|row| row.contains("3 /usr/bin")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn status_line_tags_the_active_modes() {
+        let mut app = app_with(vec![
+            Environment::new("A".into(), "1".into()),
+            Environment::new("B".into(), "2".into()),
+        ]);
+        assert_eq!(app.mode_tags(), ["[sort: none ↑]", "[mask]"]);
+
+        press(&mut app, "m/A");
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let rows = draw(&mut app, 60, 12);
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("[sort: none ↑] [/A key fuzzy")),
+            "{rows:#?}"
+        );
+        assert!(!rows.iter().any(|row| row.contains("[mask]")), "{rows:#?}");
+    }
+
+    #[test]
+    fn picked_variables_are_checked() {
+        let mut app = app_with(vec![
+            Environment::new("AWS_REGION".into(), "eu".into()),
+            Environment::new("HOME".into(), "/home/user".into()),
+        ]);
+        press(&mut app, "M^AWS_");
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let rows = draw(&mut app, 40, 12);
+        assert!(
+            rows.iter().any(|row| row.contains("✓ AWS_REGION")),
+            "{rows:#?}"
+        );
+        assert!(rows.iter().any(|row| row.contains("  HOME")), "{rows:#?}");
+    }
+
+    #[test]
+    fn the_highlight_symbol_marks_the_selection() {
+        use super::super::Highlight;
+
+        let (highlight, _) = Highlight::from_toml(
+            "[list]\nhighlight_symbol = \"▶ \"\nhighlight_spacing = \"when_selected\"",
+        );
+        let mut app = app_with(vec![Environment::new("FIRST".into(), "1".into())]);
+        app.highlight = highlight;
+        let rows = draw(&mut app, 40, 10);
+        assert!(rows.iter().any(|row| row.starts_with("FIRST")), "{rows:#?}");
+        press(&mut app, "j");
+        let rows = draw(&mut app, 40, 10);
+        assert!(
+            rows.iter().any(|row| row.starts_with("▶ FIRST")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn referrers_of_the_value_are_listed_by_key() {
+        let mut app = app_with(vec![
+            Environment::new("CARGO_HOME".into(), "/opt/cargo".into()),
+            Environment::new("PATH".into(), "/opt/cargo/bin:/bin".into()),
+            Environment::new("OTHER".into(), "/opt".into()),
+        ]);
+        press(&mut app, "jX");
+        let rows = draw(&mut app, 50, 16);
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("found in the value of 1 variable")),
+            "{rows:#?}"
+        );
+        assert!(rows.iter().any(|row| row.trim() == "PATH"), "{rows:#?}");
+
+        press(&mut app, "jj");
+        let rows = draw(&mut app, 50, 16);
+        assert!(
+            rows.iter()
+                .any(|row| row.contains("found in the values of 2 variables")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn the_list_can_show_values_in_place_of_keys() {
+        let mut app = app_with(vec![
+            Environment::new("EDITOR".into(), "vim".into()),
+            Environment::new("API_TOKEN".into(), "hunter2".into()),
+        ]);
+        press(&mut app, "K");
+        let rows = draw(&mut app, 40, 12);
+        assert!(rows.iter().any(|row| row.starts_with(" vim")), "{rows:#?}");
+        assert!(
+            rows.iter().any(|row| row.starts_with(" ••••••")),
+            "{rows:#?}"
+        );
+        assert!(!rows.iter().any(|row| row.contains("EDITOR")), "{rows:#?}");
+
+        press(&mut app, "j");
+        let rows = draw(&mut app, 40, 12);
+        assert!(
+            rows.iter().any(|row| row.contains("EDITOR (3 bytes")),
+            "{rows:#?}"
+        );
+        assert_eq!(selected_key(&app), Some("EDITOR"));
+    }
+
+    #[test]
+    fn well_known_variables_are_described() {
+        let mut app = app_with(vec![
+            Environment::new("PATH".into(), "/bin".into()),
+            Environment::new("MY_TOOL_HOME".into(), "/opt/tool".into()),
+        ]);
+        press(&mut app, "j");
+        let rows = draw(&mut app, 60, 16);
+        assert!(
+            rows.iter()
+                .any(|row| row.trim() == "directories searched for commands, in order"),
+            "{rows:#?}"
+        );
+        press(&mut app, "j");
+        let rows = draw(&mut app, 60, 16);
+        assert!(
+            !rows.iter().any(|row| row.contains("directories searched")),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn value_pane_shows_the_wrapped_position() {
+        let value = (1..=30)
+            .map(|n| format!("word{n}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut app = app_with(vec![Environment::new("LONG".into(), value)]);
+        press(&mut app, "j");
+        app.on_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        let rows = draw(&mut app, 30, 14);
+        assert!(
+            rows.iter().any(|row| row.ends_with("line 1-4 of 8")),
+            "{rows:#?}"
+        );
+
+        // scrolling stops at the end of the wrapped value.
+        press(&mut app, "Gjj");
+        let rows = draw(&mut app, 30, 14);
+        assert!(
+            rows.iter().any(|row| row.ends_with("line 5-8 of 8")),
+            "{rows:#?}"
+        );
+        assert!(
+            rows.iter().any(|row| row.trim() == "word29 word30"),
+            "{rows:#?}"
+        );
+    }
+
+    #[test]
+    fn masked_secrets_are_left_out_of_references() {
+        let mut app = app_with(vec![
+            Environment::new("API_TOKEN".into(), "hunter2".into()),
+            Environment::new("COPY".into(), "hunter2".into()),
+        ]);
+        app.cross_references = true;
+        let copy = Environment::new("COPY".into(), "hunter2".into());
+        assert!(app.cross_references(&copy, "hunter2").is_empty());
+        press(&mut app, "jj");
+        assert_eq!(selected_key(&app), Some("COPY"));
+        assert_eq!(app.find_referrers(), Some(&[][..]));
+
+        press(&mut app, "m");
+        assert_eq!(app.cross_references(&copy, "hunter2").len(), 7);
+        assert_eq!(app.find_referrers(), Some(&["API_TOKEN".to_string()][..]));
+    }
+
+    #[test]
+    fn every_fixed_key_is_in_the_help() {
+        let app = app_with(Vec::new());
+        let help = app.help_lines();
+        let listed: Vec<&str> = help.iter().flat_map(|(keys, _)| keys.split(", ")).collect();
+        for key in crate::keymap::FIXED_KEYS {
+            // the delete key is typed twice, and listed as the pair.
+            assert!(
+                listed.contains(key) || listed.contains(&key.repeat(2).as_str()),
+                "{key} is missing from the help"
+            );
+        }
+        for key in ["tab", "ctrl-r", "ctrl-f"] {
+            assert!(
+                help.iter()
+                    .any(|(keys, _)| *keys == format!("{key}, in the search")),
+                "{key} is missing from the search help"
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use clap::Parser;
+use envirust::Shell;
+use std::path::PathBuf;
+
+/// A simple environment reader.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Show the variables of a .env file instead of the process environment.
+    #[arg(long, value_name = "PATH", conflicts_with = "diff")]
+    pub file: Option<PathBuf>,
+
+    /// Show how the process environment differs from a .env file.
+    #[arg(long, value_name = "PATH")]
+    pub diff: Option<PathBuf>,
+
+    /// Show how the process environment differs from a snapshot saved with 'W'.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "diff", "baseline"])]
+    pub compare: Option<PathBuf>,
+
+    /// Mark the variables as new, overridden or inherited compared to a .env file.
+    #[arg(long, value_name = "PATH", conflicts_with = "diff")]
+    pub baseline: Option<PathBuf>,
+
+    /// Start with the list filtered by a search query.
+    #[arg(long, value_name = "QUERY")]
+    pub filter: Option<String>,
+
+    /// Start with only the variables whose keys start with PREFIX.
+    #[arg(long, value_name = "PREFIX")]
+    pub prefix: Option<String>,
+
+    /// Start with the list sorted by key.
+    #[arg(long)]
+    pub sort: bool,
+
+    /// The shell syntax used when exporting a single variable.
+    #[arg(long, value_enum, default_value_t)]
+    pub shell: Shell,
+
+    /// Read the variables again a few times a second, flashing the ones that changed.
+    #[arg(long, conflicts_with_all = ["json", "list"])]
+    pub watch: bool,
+
+    /// Draw the UI in the default colors of the terminal, like with NO_COLOR set.
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Write the values of secrets instead of redacting them.
+    ///
+    /// Secrets are the variables matching the masking patterns. While masking
+    /// is on, exports from the TUI, --list and --json write <redacted> for
+    /// their values, and --list and --json say on stderr how many were.
+    #[arg(long)]
+    pub include_secrets: bool,
+
+    /// Draw the UI on stderr and print the changes made in it as statements
+    /// for the shell on quit, to run as `eval "$(envirust --eval)"`.
+    #[arg(long, conflicts_with_all = ["diff", "compare", "json", "list"])]
+    pub eval: bool,
+
+    /// Print the variables as JSON and exit instead of starting the TUI.
+    #[arg(long, conflicts_with = "list")]
+    pub json: bool,
+
+    /// Print the variables as KEY=VALUE lines and exit instead of starting the TUI.
+    #[arg(long)]
+    pub list: bool,
+}
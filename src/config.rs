@@ -0,0 +1,52 @@
+use std::{env, fs, io, path::PathBuf};
+
+/// the file with the theme and masking settings, inside the config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The directory holding the config files, `$XDG_CONFIG_HOME/envirust` or
+/// `~/.config/envirust`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("envirust"))
+}
+
+/// The directory holding saved state, `$XDG_STATE_HOME/envirust` or
+/// `~/.local/state/envirust`.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("envirust"))
+}
+
+/// Read the config file `name`, returning `None` if it does not exist.
+pub fn read_config(name: &str) -> io::Result<Option<String>> {
+    read_optional(config_dir().map(|dir| dir.join(name)))
+}
+
+/// Read the state file `name`, returning `None` if it does not exist.
+pub fn read_state(name: &str) -> io::Result<Option<String>> {
+    read_optional(state_dir().map(|dir| dir.join(name)))
+}
+
+/// Write the state file `name`, creating the state directory if needed.
+pub fn write_state(name: &str, contents: &str) -> io::Result<()> {
+    let dir = state_dir().ok_or_else(|| io::Error::other("no state directory, HOME is not set"))?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), contents)
+}
+
+fn read_optional(path: Option<PathBuf>) -> io::Result<Option<String>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
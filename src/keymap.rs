@@ -0,0 +1,410 @@
+use crate::config;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt::Display;
+
+/// the file the key bindings are read from, inside the config directory.
+const KEYS_FILE: &str = "keys.toml";
+
+/// the keys handled by envirust itself, which no action can be bound to.
+pub const FIXED_KEYS: &[&str] = &[
+    "h", "left", "l", "right", "tab", "ctrl-p", "V", "s", "S", "Y", "ctrl-y", "A", "space", "M",
+    "u", "w", "J", "E", "W", "m", "enter", "#", "c", "K", "+", "-", "C", "z", "H", "L", "f", "B",
+    "x", "X", "v", "R", "F", "o", "U", "t", "T", "b", "*", "e", "a", "d", "p", "r", "Q", "D",
+    "ctrl-t", "P", "n", "N", "?", "i",
+];
+
+/// actions that can be rebound in `keys.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Next,
+    Previous,
+    First,
+    Last,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Search,
+    Copy,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Self::Quit,
+        Self::Next,
+        Self::Previous,
+        Self::First,
+        Self::Last,
+        Self::HalfPageDown,
+        Self::HalfPageUp,
+        Self::PageDown,
+        Self::PageUp,
+        Self::Search,
+        Self::Copy,
+    ];
+
+    /// The name used for the action in `keys.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Next => "next",
+            Self::Previous => "previous",
+            Self::First => "first",
+            Self::Last => "last",
+            Self::HalfPageDown => "half_page_down",
+            Self::HalfPageUp => "half_page_up",
+            Self::PageDown => "page_down",
+            Self::PageUp => "page_up",
+            Self::Search => "search",
+            Self::Copy => "copy",
+        }
+    }
+
+    /// What the action does, as shown in the help.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Next => "select the next variable",
+            Self::Previous => "select the previous variable",
+            Self::First => "select the first variable",
+            Self::Last => "select the last variable",
+            Self::HalfPageDown => "move down half a page",
+            Self::HalfPageUp => "move up half a page",
+            Self::PageDown => "move down a page",
+            Self::PageUp => "move up a page",
+            Self::Search => "search",
+            Self::Copy => "copy the KEY=VALUE pair when pressed twice, like yy",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Quit => &["q", "esc", "ctrl-c"],
+            Self::Next => &["j", "down"],
+            Self::Previous => &["k", "up"],
+            Self::First => &["g", "home"],
+            Self::Last => &["G", "end"],
+            Self::HalfPageDown => &["ctrl-d"],
+            Self::HalfPageUp => &["ctrl-u"],
+            Self::PageDown => &["pagedown"],
+            Self::PageUp => &["pageup"],
+            Self::Search => &["/"],
+            Self::Copy => &["y"],
+        }
+    }
+}
+
+/// a key and its modifiers, written like `q`, `G`, `ctrl-c` or `pagedown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    modifiers: KeyModifiers,
+    code: KeyCode,
+}
+
+impl Key {
+    fn new(modifiers: KeyModifiers, code: KeyCode) -> Self {
+        // the case of a char already tells whether shift was held.
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { modifiers, code }
+    }
+
+    fn from_event(event: &KeyEvent) -> Self {
+        Self::new(event.modifiers, event.code)
+    }
+
+    /// Whether envirust handles the key itself: one of [`FIXED_KEYS`], a
+    /// digit typing a count, or a letter with Alt jumping through the list.
+    fn is_fixed(&self) -> bool {
+        let plain = !self
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match self.code {
+            KeyCode::Char('1'..='9') if plain => true,
+            KeyCode::Char(_) if self.modifiers.contains(KeyModifiers::ALT) => true,
+            _ => FIXED_KEYS
+                .iter()
+                .any(|name| Key::parse(name) == Some(*self)),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, tail)| !tail.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "esc" => KeyCode::Esc,
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(Self::new(modifiers, code))
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => write!(f, "{}", code.to_string().to_lowercase().replace(' ', "")),
+        }
+    }
+}
+
+/// the keys bound to each rebindable action.
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .flat_map(|action| default_bindings(action).map(move |key| (key, action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Load the key bindings from `keys.toml` in the config directory.
+    ///
+    /// Returns the key map along with warnings about bindings that could not
+    /// be used and were replaced by the defaults.
+    pub fn load() -> (Self, Vec<String>) {
+        match config::read_config(KEYS_FILE) {
+            Ok(Some(contents)) => Self::from_toml(&contents),
+            Ok(None) => (Self::default(), Vec::new()),
+            Err(err) => (
+                Self::default(),
+                vec![format!("could not read {KEYS_FILE}: {err}")],
+            ),
+        }
+    }
+
+    /// Parse key bindings written as `action = "key"` or `action = ["key", ...]`.
+    fn from_toml(contents: &str) -> (Self, Vec<String>) {
+        let table: toml::Table = match toml::from_str(contents) {
+            Ok(table) => table,
+            Err(err) => {
+                let error = format!("invalid {KEYS_FILE}: {}", err.message());
+                return (Self::default(), vec![error]);
+            }
+        };
+        let mut warnings = Vec::new();
+        let mut configured: Vec<(Action, Vec<Key>)> = Vec::new();
+
+        for (name, value) in &table {
+            let Some(action) = Action::from_name(name) else {
+                warnings.push(format!("unknown action '{name}'"));
+                continue;
+            };
+            let names: Vec<&str> = match value {
+                toml::Value::String(key) => vec![key.as_str()],
+                toml::Value::Array(keys) => keys.iter().filter_map(|key| key.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            let keys: Option<Vec<Key>> = names.iter().map(|key| Key::parse(key)).collect();
+            match keys {
+                Some(keys) if !keys.is_empty() => configured.push((action, keys)),
+                _ => warnings.push(format!("invalid keys for '{name}', using defaults")),
+            }
+        }
+
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+        for action in Action::ALL {
+            match configured
+                .iter()
+                .find(|(configured, _)| *configured == action)
+            {
+                Some((_, keys)) => bindings.extend(keys.iter().map(|&key| (key, action))),
+                None => bindings.extend(default_bindings(action).map(|key| (key, action))),
+            }
+        }
+        if let Some((key, action)) = bindings.iter().find(|(key, _)| key.is_fixed()) {
+            warnings.push(format!(
+                "'{key}' is a fixed key and cannot be bound to '{}', using default keys",
+                action.name()
+            ));
+            return (Self::default(), warnings);
+        }
+        for (i, (key, action)) in bindings.iter().enumerate() {
+            let conflict = bindings[..i]
+                .iter()
+                .find(|(other_key, other)| other_key == key && other != action);
+            if let Some((_, other)) = conflict {
+                warnings.push(format!(
+                    "'{key}' is bound to both '{}' and '{}', using default keys",
+                    other.name(),
+                    action.name()
+                ));
+                return (Self::default(), warnings);
+            }
+        }
+        (Self { bindings }, warnings)
+    }
+
+    /// The keys bound to `action`.
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = Key> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|&(key, _)| key)
+    }
+
+    /// The action bound to the pressed key, if any.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|&(_, action)| action)
+    }
+}
+
+fn default_bindings(action: Action) -> impl Iterator<Item = Key> {
+    action
+        .default_keys()
+        .iter()
+        .map(|key| Key::parse(key).expect("default keys are valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_keys_cannot_be_bound() {
+        for keys in [
+            "next = \"s\"",
+            "copy = \"d\"",
+            "search = \"alt-x\"",
+            "last = \"5\"",
+        ] {
+            let (keymap, warnings) = KeyMap::from_toml(keys);
+            assert_eq!(warnings.len(), 1, "{keys}: {warnings:?}");
+            assert!(warnings[0].contains("is a fixed key"), "{warnings:?}");
+            assert_eq!(keymap.bindings, KeyMap::default().bindings);
+        }
+
+        let (keymap, warnings) = KeyMap::from_toml("next = [\"ctrl-s\", \"0\"]");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let event = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&event), Some(Action::Next));
+    }
+
+    #[test]
+    fn actions_can_be_rebound() {
+        let (keymap, warnings) = KeyMap::from_toml("quit = \"Z\"\nnext = [\"ctrl-n\", \"down\"]");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let press = |modifiers, code| keymap.action(&KeyEvent::new(code, modifiers));
+        assert_eq!(
+            press(KeyModifiers::NONE, KeyCode::Char('Z')),
+            Some(Action::Quit)
+        );
+        assert_eq!(press(KeyModifiers::NONE, KeyCode::Char('q')), None);
+        assert_eq!(
+            press(KeyModifiers::CONTROL, KeyCode::Char('n')),
+            Some(Action::Next)
+        );
+        // actions left out keep their defaults.
+        assert_eq!(
+            press(KeyModifiers::NONE, KeyCode::Char('k')),
+            Some(Action::Previous)
+        );
+    }
+
+    #[test]
+    fn conflicting_bindings_fall_back_to_the_defaults() {
+        let (keymap, warnings) = KeyMap::from_toml("quit = \"Z\"\nnext = \"k\"");
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("'k' is bound to both"), "{warnings:?}");
+        assert_eq!(keymap.bindings, KeyMap::default().bindings);
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_reported() {
+        let (keymap, warnings) =
+            KeyMap::from_toml("jump = \"x\"\nsearch = \"hyper-x\"\ncopy = \"O\"");
+        assert_eq!(
+            warnings,
+            [
+                "unknown action 'jump'",
+                "invalid keys for 'search', using defaults"
+            ]
+        );
+        let keys: Vec<String> = keymap
+            .keys(Action::Search)
+            .map(|key| key.to_string())
+            .collect();
+        assert_eq!(keys, ["/"]);
+
+        let (_, warnings) = KeyMap::from_toml("quit = ");
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].starts_with("invalid keys.toml"), "{warnings:?}");
+    }
+
+    #[test]
+    fn keys_are_written_like_they_are_parsed() {
+        for text in [
+            "q",
+            "G",
+            "ctrl-c",
+            "alt-x",
+            "pagedown",
+            "space",
+            "f5",
+            "shift-tab",
+        ] {
+            let key = Key::parse(text).expect(text);
+            assert_eq!(key.to_string(), text);
+        }
+        // the case of a char already tells about shift.
+        assert_eq!(Key::parse("shift-g").unwrap().to_string(), "g");
+        assert_eq!(Key::parse("ctrl-"), None);
+    }
+}
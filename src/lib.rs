@@ -0,0 +1,33 @@
+//! Read, filter and sort environment variables.
+//!
+//! This is the library behind the `envirust` terminal UI. Other tools can use
+//! it to load variables from the process environment or a `.env` file and
+//! narrow them down the same way the UI does:
+//!
+//! ```
+//! use envirust::{
+//!     EnvList, ProcessEnv, Search, SearchMode, SearchTarget, SortDirection, SortMode,
+//! };
+//!
+//! let mut list = EnvList::new(Box::new(ProcessEnv))?;
+//! list.filter(&Search::new("PATH", SearchTarget::Key, SearchMode::Fuzzy).ignoring_case());
+//! list.sort_by(SortMode::KeyAsc, SortDirection::Ascending);
+//! for variable in list.shown() {
+//!     println!("{variable}");
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+#![warn(missing_docs)]
+
+mod app;
+mod config;
+mod keymap;
+mod model;
+mod state;
+mod theme;
+
+pub use app::App;
+pub use model::{
+    DiffEnv, DiffStatus, EnvList, EnvSource, Environment, FileEnv, ProcessEnv, Search, SearchMode,
+    SearchTarget, Shell, SnapshotFile, SortDirection, SortMode, get_variables,
+};
@@ -1,12 +1,118 @@
-use app::App;
-use color_eyre::eyre::Result;
+use clap::Parser;
+use cli::Cli;
+use color_eyre::eyre::{Result, eyre};
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use envirust::App;
+use ratatui::{Terminal, backend::CrosstermBackend};
+use std::io::{self, stderr, stdout};
 
-mod app;
+mod cli;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
+    let cli = Cli::parse();
+    let mut app = if let Some(path) = &cli.file {
+        App::from_file(path).map_err(|err| eyre!("could not read {}: {err}", path.display()))?
+    } else if let Some(path) = &cli.diff {
+        App::diff_file(path).map_err(|err| eyre!("could not read {}: {err}", path.display()))?
+    } else if let Some(path) = &cli.compare {
+        App::compare_snapshot(path)
+            .map_err(|err| eyre!("could not read {}: {err}", path.display()))?
+    } else {
+        App::new()
+    };
+    // the saved sort and masking are for the TUI, the printed output keeps the defaults.
+    if !cli.json && !cli.list {
+        app = app.restoring_state();
+    }
+    if let Some(path) = &cli.baseline {
+        app = app
+            .with_baseline(path)
+            .map_err(|err| eyre!("could not read {}: {err}", path.display()))?;
+    }
+    if let Some(query) = &cli.filter {
+        app = app.with_filter(query);
+    }
+    if let Some(prefix) = &cli.prefix {
+        app = app.with_prefix(prefix);
+    }
+    if cli.sort {
+        app = app.sorted();
+    }
+    app = app.with_shell(cli.shell);
+    if cli.include_secrets {
+        app = app.including_secrets();
+    }
+    if cli.no_color {
+        app = app.without_color();
+    }
+    if cli.watch {
+        app = app.watching();
+    }
+    if cli.eval {
+        app = app.evaluating();
+    }
+
+    if cli.json || cli.list {
+        // stderr, so scripts reading stdout see why a value is not there.
+        let redacted = app.redacted_count();
+        if redacted > 0 {
+            eprintln!(
+                "envirust: {redacted} secret values redacted, pass --include-secrets to print them"
+            );
+        }
+    }
+    if cli.json {
+        println!("{}", app.to_json()?);
+        return Ok(());
+    }
+    if cli.list {
+        return match app.write_list(&mut stdout().lock()) {
+            // the reader went away, as with `envirust --list | head`.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        };
+    }
+
+    if cli.eval {
+        run_on_stderr(&mut app)?;
+        app.write_eval(&mut stdout().lock(), &mut stderr().lock())?;
+        return Ok(());
+    }
+
     let terminal = ratatui::init();
-    let app_result = App::new().run(terminal);
+    execute!(stdout(), EnableMouseCapture)?;
+    let app_result = app.run(terminal);
+    execute!(stdout(), DisableMouseCapture)?;
     ratatui::restore();
-    app_result
+    app_result?;
+    if let Some(mut shell) = app.shell_command() {
+        shell
+            .status()
+            .map_err(|err| eyre!("could not start {:?}: {err}", shell.get_program()))?;
+    }
+    Ok(())
+}
+
+/// Run the UI on stderr, leaving stdout to the statements printed with
+/// `--eval`, which the calling shell captures.
+fn run_on_stderr(app: &mut App) -> Result<()> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stderr(), DisableMouseCapture, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        hook(info);
+    }));
+    terminal::enable_raw_mode()?;
+    execute!(stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+    let result = Terminal::new(CrosstermBackend::new(stderr()))
+        .map_err(Into::into)
+        .and_then(|terminal| app.run(terminal));
+    execute!(stderr(), DisableMouseCapture, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
 }
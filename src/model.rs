@@ -0,0 +1,2014 @@
+use crate::{config, theme::Theme};
+use ratatui::{
+    style::Color,
+    widgets::{ListState, ScrollbarState},
+};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// keys always shown one path component per line.
+pub(crate) const PATH_KEYS: &[&str] = &[
+    "PATH",
+    "LD_LIBRARY_PATH",
+    "PYTHONPATH",
+    "MANPATH",
+    "INFOPATH",
+    "XDG_DATA_DIRS",
+    "XDG_CONFIG_DIRS",
+];
+
+/// how long a variable that changed while watching stays highlighted.
+pub(crate) const FLASH_DURATION: Duration = Duration::from_secs(1);
+
+/// the delimiters keys can be grouped by, in the order they are cycled.
+pub(crate) const GROUP_DELIMITERS: [char; 3] = ['_', '.', '-'];
+
+/// the file bookmarked keys are saved to, inside the state directory.
+pub(crate) const BOOKMARKS_FILE: &str = "bookmarks";
+
+/// variables marked for quick access, tracked by key so they survive reloads.
+#[derive(Debug, Default)]
+pub(crate) struct Bookmarks {
+    pub(crate) keys: HashSet<String>,
+    /// whether only bookmarked variables are shown.
+    pub(crate) only: bool,
+}
+
+impl Bookmarks {
+    /// Read the bookmarked keys saved in the state directory, one per line.
+    pub(crate) fn load() -> io::Result<Self> {
+        let keys = config::read_state(BOOKMARKS_FILE)?
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self { keys, only: false })
+    }
+
+    /// Write the bookmarked keys to the state directory.
+    pub(crate) fn save(&self) -> io::Result<()> {
+        let mut keys: Vec<&str> = self.keys.iter().map(String::as_str).collect();
+        keys.sort_unstable();
+        let mut contents = keys.join("\n");
+        contents.push('\n');
+        config::write_state(BOOKMARKS_FILE, &contents)
+    }
+
+    /// Bookmark `key`, or remove its bookmark. Returns whether it is now bookmarked.
+    pub(crate) fn toggle(&mut self, key: &str) -> bool {
+        if self.keys.remove(key) {
+            false
+        } else {
+            self.keys.insert(key.to_string());
+            true
+        }
+    }
+
+    pub(crate) fn contains(&self, item: &Environment) -> bool {
+        self.keys.contains(&item.key)
+    }
+
+    /// Whether `item` passes the bookmarks filter.
+    pub(crate) fn shows(&self, item: &Environment) -> bool {
+        !self.only || self.contains(item)
+    }
+}
+
+/// where the variables are read from.
+pub trait EnvSource {
+    /// Read the variables, along with the number of malformed lines skipped.
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)>;
+
+    /// Whether the variables can be added, edited and deleted.
+    fn editable(&self) -> bool {
+        true
+    }
+
+    /// Set the variable `key` at the source. Edits stay in the list by default.
+    fn set_var(&self, _key: &str, _value: &str) {}
+
+    /// Remove the variable `key` at the source. Deletions stay in the list by
+    /// default.
+    fn remove_var(&self, _key: &str) {}
+}
+
+/// the environment of this process.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcessEnv;
+
+impl EnvSource for ProcessEnv {
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        Ok((get_variables(), 0))
+    }
+
+    fn set_var(&self, key: &str, value: &str) {
+        // SAFETY: envirust does not spawn threads that read the environment.
+        unsafe { env::set_var(key, value) };
+    }
+
+    fn remove_var(&self, key: &str) {
+        // SAFETY: envirust does not spawn threads that read the environment.
+        unsafe { env::remove_var(key) };
+    }
+}
+
+/// a `.env` file.
+#[derive(Debug, Clone)]
+pub struct FileEnv {
+    path: PathBuf,
+}
+
+impl FileEnv {
+    /// A source reading the `.env` file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EnvSource for FileEnv {
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        Ok(parse_dotenv(&fs::read_to_string(&self.path)?))
+    }
+}
+
+/// the variables saved by `envirust` in a snapshot file.
+#[derive(Debug, Clone)]
+pub struct SnapshotFile {
+    path: PathBuf,
+}
+
+impl SnapshotFile {
+    /// A source reading the snapshot at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EnvSource for SnapshotFile {
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        parse_snapshot(&fs::read_to_string(&self.path)?)
+    }
+}
+
+/// the differences between this process and a `.env` file or a snapshot.
+#[derive(Debug, Clone)]
+pub struct DiffEnv<S = FileEnv> {
+    file: S,
+}
+
+impl DiffEnv {
+    /// A source comparing this process against the `.env` file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            file: FileEnv::new(path),
+        }
+    }
+}
+
+impl DiffEnv<SnapshotFile> {
+    /// A source comparing this process against the snapshot at `path`.
+    pub fn snapshot(path: impl Into<PathBuf>) -> Self {
+        Self {
+            file: SnapshotFile::new(path),
+        }
+    }
+}
+
+impl<S: EnvSource> EnvSource for DiffEnv<S> {
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        let (items, skipped) = self.file.load()?;
+        Ok((diff_variables(get_variables(), items), skipped))
+    }
+
+    fn editable(&self) -> bool {
+        false
+    }
+}
+
+/// variables kept in memory, like ones built by another tool.
+impl EnvSource for Vec<Environment> {
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        Ok((self.clone(), 0))
+    }
+}
+
+/// the environment list.
+pub struct EnvList {
+    pub(crate) source: Box<dyn EnvSource>,
+    pub(crate) items: Vec<Environment>,
+    /// indices into `items` that are currently shown.
+    pub(crate) view: Vec<usize>,
+    pub(crate) state: ListState,
+    /// kept in sync with the list offset on every render.
+    pub(crate) scrollbar: ScrollbarState,
+    pub(crate) sort: SortMode,
+    /// reverses the natural order of `sort`.
+    pub(crate) direction: SortDirection,
+    /// whether variables sharing a key prefix are shown together under a header.
+    pub(crate) grouped: bool,
+    /// what separates the prefix of a key from the rest.
+    pub(crate) delimiter: char,
+    /// the number of malformed lines skipped while loading a file.
+    pub(crate) skipped: usize,
+    /// whether variables were changed since they were loaded.
+    pub(crate) edited: bool,
+    /// how many lines the value pane is scrolled down.
+    pub(crate) value_scroll: u16,
+    /// how many columns the value pane is scrolled right, when not wrapping.
+    pub(crate) value_hscroll: u16,
+    /// the height of the value pane in the last render.
+    pub(crate) value_height: u16,
+    /// when each variable last changed while watching.
+    pub(crate) flashes: HashMap<String, Instant>,
+    /// whether the view changed since the list was last drawn.
+    pub(crate) dirty: bool,
+    /// the variables the loaded ones are marked against, if any.
+    pub(crate) baseline: Option<Baseline>,
+    /// the indices into `items` of the search matches, when jumping
+    /// between them.
+    pub(crate) hits: Vec<usize>,
+}
+
+/// a snapshot of variables to tell the ones set since from the inherited ones.
+#[derive(Debug, Clone)]
+pub(crate) struct Baseline {
+    /// where the snapshot came from, like a file name.
+    pub(crate) origin: String,
+    pub(crate) items: Vec<Environment>,
+}
+
+impl EnvList {
+    /// Read the variables from `source`, showing all of them.
+    pub fn new(source: Box<dyn EnvSource>) -> io::Result<Self> {
+        let (items, skipped) = load_merged(source.as_ref())?;
+        let view = (0..items.len()).collect();
+        Ok(Self {
+            source,
+            items,
+            view,
+            state: ListState::default(),
+            scrollbar: ScrollbarState::default(),
+            sort: SortMode::default(),
+            direction: SortDirection::default(),
+            grouped: false,
+            delimiter: GROUP_DELIMITERS[0],
+            skipped,
+            edited: false,
+            value_scroll: 0,
+            value_hscroll: 0,
+            value_height: 0,
+            flashes: HashMap::new(),
+            dirty: true,
+            baseline: None,
+            hits: Vec::new(),
+        })
+    }
+
+    /// Mark the variables by how they differ from `baseline`, or stop
+    /// marking them.
+    pub(crate) fn set_baseline(
+        &mut self,
+        baseline: Option<Baseline>,
+        search: &Search,
+        bookmarks: &Bookmarks,
+    ) {
+        if self.baseline.is_some() {
+            // drop the marks of the previous baseline.
+            self.items = self.snapshot();
+        }
+        self.baseline = baseline;
+        self.remark();
+        self.apply_filter(search, bookmarks);
+    }
+
+    /// The variables as they are now, to be used as a baseline.
+    pub(crate) fn snapshot(&self) -> Vec<Environment> {
+        self.items
+            .iter()
+            .filter(|item| item.status != Some(DiffStatus::Removed))
+            .map(|item| Environment {
+                status: None,
+                ..item.clone()
+            })
+            .collect()
+    }
+
+    /// `items` marked against the baseline, if there is one.
+    fn marked(&self, items: Vec<Environment>) -> Vec<Environment> {
+        let Some(baseline) = &self.baseline else {
+            return items;
+        };
+        let live = items
+            .into_iter()
+            .filter(|item| item.status != Some(DiffStatus::Removed))
+            .collect();
+        compare_variables(live, baseline.items.clone())
+    }
+
+    /// Mark the variables against the baseline again after they changed.
+    fn remark(&mut self) {
+        let items = std::mem::take(&mut self.items);
+        self.items = self.marked(items);
+    }
+
+    /// Read the variables from the source, marked against the baseline.
+    fn load(&self) -> io::Result<(Vec<Environment>, usize)> {
+        let (items, skipped) = load_merged(self.source.as_ref())?;
+        Ok((self.marked(items), skipped))
+    }
+
+    /// Set the value of the variable `key`, adding it if it is missing.
+    pub(crate) fn set_value(
+        &mut self,
+        key: &str,
+        value: String,
+        search: &Search,
+        bookmarks: &Bookmarks,
+    ) {
+        match self.items.iter_mut().find(|item| item.key == key) {
+            Some(item) => item.value = value,
+            None => self.items.push(Environment::new(key.to_string(), value)),
+        }
+        self.edited = true;
+        self.remark();
+        self.apply_filter(search, bookmarks);
+    }
+
+    /// Remove the variable `key`, selecting the item that took its place or
+    /// the new last item if it was last.
+    pub(crate) fn remove(&mut self, key: &str, search: &Search, bookmarks: &Bookmarks) {
+        let position = self.state.selected();
+        self.items.retain(|item| item.key != key);
+        self.edited = true;
+        self.remark();
+        self.filter_view(search, bookmarks);
+        let last = self.view.len().checked_sub(1);
+        self.state
+            .select(position.zip(last).map(|(i, last)| i.min(last)));
+    }
+
+    /// Read the variables from the source again, keeping the selected
+    /// variable highlighted if it is still present.
+    pub(crate) fn reload(&mut self, search: &Search, bookmarks: &Bookmarks) -> io::Result<()> {
+        let selected = self.selected().map(|item| item.key.clone());
+        (self.items, self.skipped) = self.load()?;
+        self.filter_view(search, bookmarks);
+        self.select_key(selected.as_deref());
+        Ok(())
+    }
+
+    /// Read the variables again if they changed, flashing the ones whose
+    /// values are new. The selection and scroll position are kept.
+    pub(crate) fn watch(&mut self, search: &Search, bookmarks: &Bookmarks) -> io::Result<()> {
+        let now = Instant::now();
+        let flashing = self.flashes.len();
+        self.flashes
+            .retain(|_, changed| now - *changed < FLASH_DURATION);
+        if self.flashes.len() != flashing {
+            self.dirty = true;
+        }
+        let (items, skipped) = self.load()?;
+        if items == self.items {
+            return Ok(());
+        }
+        let previous: HashMap<&str, &str> = self
+            .items
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str()))
+            .collect();
+        for item in &items {
+            if previous.get(item.key.as_str()) != Some(&item.value.as_str()) {
+                self.flashes.insert(item.key.clone(), now);
+            }
+        }
+
+        let selected = self.selected().map(|item| item.key.clone());
+        let offset = self.state.offset();
+        (self.items, self.skipped) = (items, skipped);
+        self.filter_view(search, bookmarks);
+        self.select_key(selected.as_deref());
+        *self.state.offset_mut() = offset;
+        Ok(())
+    }
+
+    /// How long ago `item` changed while watching, if recently.
+    pub(crate) fn flash(&self, item: &Environment) -> Option<Duration> {
+        self.flashes
+            .get(&item.key)
+            .map(|changed| changed.elapsed())
+            .filter(|&elapsed| elapsed < FLASH_DURATION)
+    }
+
+    /// Select the next shown variable after the selection whose key starts
+    /// with `letter`, ignoring case and wrapping around.
+    pub(crate) fn jump_to(&mut self, letter: char) {
+        let len = self.view.len();
+        let start = self.state.selected().map_or(0, |i| i + 1);
+        let found = (0..len).map(|n| (start + n) % len).find(|&position| {
+            let key = &self.items[self.view[position]].key;
+            key.chars()
+                .next()
+                .is_some_and(|first| chars_eq(first, letter, false))
+        });
+        if found.is_some() {
+            self.state.select(found);
+        }
+    }
+
+    /// Whether the variable at view `position` is a search match.
+    pub(crate) fn is_hit(&self, position: usize) -> bool {
+        self.hits.binary_search(&self.view[position]).is_ok()
+    }
+
+    /// Select the next search match after the selection, or the one before
+    /// it unless `forward`, wrapping around. Returns whether there is one.
+    pub(crate) fn jump_to_hit(&mut self, forward: bool) -> bool {
+        let len = self.view.len();
+        let start = match (self.state.selected(), forward) {
+            (Some(i), true) => i + 1,
+            (Some(i), false) => i + len - 1,
+            (None, true) => 0,
+            (None, false) => len.saturating_sub(1),
+        };
+        let step = |n: usize| {
+            if forward {
+                (start + n) % len
+            } else {
+                (start + len - n) % len
+            }
+        };
+        let found = (0..len).map(step).find(|&position| self.is_hit(position));
+        if found.is_some() {
+            self.state.select(found);
+        }
+        found.is_some()
+    }
+
+    /// Select the shown variable called `key`, or nothing if it is not shown.
+    pub(crate) fn select_key(&mut self, key: Option<&str>) {
+        let position = key.and_then(|key| self.view.iter().position(|&i| self.items[i].key == key));
+        self.state.select(position);
+    }
+
+    /// Show only the variables matching `search`.
+    pub fn filter(&mut self, search: &Search) {
+        self.apply_filter(search, &Bookmarks::default());
+    }
+
+    /// Order the shown variables by `sort`, reversed for
+    /// [`SortDirection::Descending`]. Variables that compare equal keep
+    /// their original order.
+    pub fn sort_by(&mut self, sort: SortMode, direction: SortDirection) {
+        self.sort = sort;
+        self.direction = direction;
+        self.resort();
+    }
+
+    /// Rebuild the view with the items matching `search` and `bookmarks`,
+    /// keeping the selected variable highlighted if it is still shown.
+    pub(crate) fn apply_filter(&mut self, search: &Search, bookmarks: &Bookmarks) {
+        let selected = self.selected().map(|item| item.key.clone());
+        self.filter_view(search, bookmarks);
+        self.select_key(selected.as_deref());
+    }
+
+    /// Rebuild the view with the items matching `search` and `bookmarks`,
+    /// leaving the selection to the caller.
+    pub(crate) fn filter_view(&mut self, search: &Search, bookmarks: &Bookmarks) {
+        self.view = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| bookmarks.shows(item) && search.shows(item))
+            .map(|(i, _)| i)
+            .collect();
+        self.hits = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| search.hits(item))
+            .map(|(i, _)| i)
+            .collect();
+        self.sort_view();
+        *self.state.offset_mut() = 0;
+    }
+
+    /// Switch to the next sort mode.
+    pub(crate) fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.resort();
+    }
+
+    /// Flip between ascending and descending order.
+    pub(crate) fn toggle_direction(&mut self) {
+        self.direction = self.direction.toggle();
+        self.resort();
+    }
+
+    /// Sort the view again, keeping the selected variable highlighted.
+    pub(crate) fn resort(&mut self) {
+        let selected = self.selected().map(|item| item.key.clone());
+        self.sort_view();
+        self.select_key(selected.as_deref());
+    }
+
+    /// Sort the view, breaking ties by insertion order.
+    ///
+    /// When grouping, the members of each group are then moved up to the
+    /// first of them, keeping their sorted order.
+    pub(crate) fn sort_view(&mut self) {
+        let items = &self.items;
+        let sort = self.sort;
+        let direction = self.direction;
+        self.view.sort_by(|&a, &b| {
+            let ordering = match sort {
+                SortMode::Insertion => a.cmp(&b),
+                _ => sort.compare(&items[a], &items[b]),
+            };
+            direction.apply(ordering).then(a.cmp(&b))
+        });
+
+        if self.grouped {
+            let delimiter = self.delimiter;
+            let mut first: HashMap<&str, usize> = HashMap::new();
+            for (position, &i) in self.view.iter().enumerate() {
+                first
+                    .entry(group_prefix(&items[i].key, delimiter))
+                    .or_insert(position);
+            }
+            self.view
+                .sort_by_key(|&i| first[group_prefix(&items[i].key, delimiter)]);
+        }
+        self.dirty = true;
+    }
+
+    /// Turn grouping by key prefix on or off.
+    pub(crate) fn toggle_grouping(&mut self) {
+        self.grouped = !self.grouped;
+        self.resort();
+    }
+
+    /// Group by the next delimiter.
+    pub(crate) fn cycle_delimiter(&mut self) {
+        let current = GROUP_DELIMITERS
+            .iter()
+            .position(|&delimiter| delimiter == self.delimiter)
+            .unwrap_or_default();
+        self.delimiter = GROUP_DELIMITERS[(current + 1) % GROUP_DELIMITERS.len()];
+        self.resort();
+    }
+
+    /// The rows drawn for the view, with a header before each group of more
+    /// than one variable when grouping.
+    pub(crate) fn rows(&self) -> Vec<Row> {
+        if !self.grouped {
+            return (0..self.view.len())
+                .map(|position| Row::Item {
+                    position,
+                    grouped: false,
+                })
+                .collect();
+        }
+        let prefix =
+            |position: usize| group_prefix(&self.items[self.view[position]].key, self.delimiter);
+        let mut rows = Vec::with_capacity(self.view.len());
+        let mut start = 0;
+        while start < self.view.len() {
+            let len = (start..self.view.len())
+                .take_while(|&position| prefix(position) == prefix(start))
+                .count();
+            let grouped = len > 1;
+            if grouped {
+                rows.push(Row::Group {
+                    prefix: prefix(start).to_string(),
+                    len,
+                });
+            }
+            rows.extend((start..start + len).map(|position| Row::Item { position, grouped }));
+            start += len;
+        }
+        rows
+    }
+
+    /// A short description of the sort order.
+    pub(crate) fn sort_label(&self) -> String {
+        let direction = match self.direction {
+            SortDirection::Ascending => self.sort.direction(),
+            SortDirection::Descending => self.sort.direction().toggle(),
+        };
+        format!("sort: {} {direction}", self.sort)
+    }
+
+    /// The shown items, in view order.
+    pub fn shown(&self) -> impl Iterator<Item = &Environment> {
+        self.view.iter().map(|&i| &self.items[i])
+    }
+
+    /// The currently selected item, if any.
+    pub fn selected(&self) -> Option<&Environment> {
+        self.state
+            .selected()
+            .and_then(|i| self.view.get(i))
+            .map(|&i| &self.items[i])
+    }
+}
+
+/// Read the variables from `source`, merging the ones set more than once.
+fn load_merged(source: &dyn EnvSource) -> io::Result<(Vec<Environment>, usize)> {
+    let (items, skipped) = source.load()?;
+    Ok((merge_duplicates(items), skipped))
+}
+
+/// Merge variables set more than once into the first of them, which takes
+/// the last value like a shell would and keeps the earlier ones in
+/// `overridden`.
+pub(crate) fn merge_duplicates(items: Vec<Environment>) -> Vec<Environment> {
+    let mut merged: Vec<Environment> = Vec::with_capacity(items.len());
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        match positions.get(&item.key) {
+            Some(&i) => {
+                let first = &mut merged[i];
+                let earlier = std::mem::replace(&mut first.value, item.value);
+                first.overridden.push(earlier);
+                first.lossy |= item.lossy;
+                // a variable stays exported once any of its lines exports it.
+                first.exported |= item.exported;
+            }
+            None => {
+                positions.insert(item.key.clone(), merged.len());
+                merged.push(item);
+            }
+        }
+    }
+    merged
+}
+
+/// a row drawn in the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Row {
+    /// the header of the `len` variables whose keys start with `prefix`.
+    Group { prefix: String, len: usize },
+    /// the variable at `position` in the view, indented when it is in a group.
+    Item { position: usize, grouped: bool },
+}
+
+/// The part of `key` before the first `delimiter`, or all of it.
+pub(crate) fn group_prefix(key: &str, delimiter: char) -> &str {
+    key.split(delimiter).next().unwrap_or(key)
+}
+
+/// what the list is sorted by.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    /// the order of `env::vars()`.
+    #[default]
+    Insertion,
+    /// keys from A to Z.
+    KeyAsc,
+    /// keys from Z to A.
+    KeyDesc,
+    /// the longest values first.
+    ValueLenDesc,
+}
+
+impl SortMode {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::Insertion => Self::KeyAsc,
+            Self::KeyAsc => Self::KeyDesc,
+            Self::KeyDesc => Self::ValueLenDesc,
+            Self::ValueLenDesc => Self::Insertion,
+        }
+    }
+
+    /// Compare two items, returning `Equal` for insertion order.
+    pub(crate) fn compare(self, a: &Environment, b: &Environment) -> std::cmp::Ordering {
+        match self {
+            Self::Insertion => std::cmp::Ordering::Equal,
+            Self::KeyAsc => a.key.cmp(&b.key),
+            Self::KeyDesc => b.key.cmp(&a.key),
+            Self::ValueLenDesc => b.value.len().cmp(&a.value.len()),
+        }
+    }
+
+    /// The natural direction of this mode.
+    pub(crate) fn direction(self) -> SortDirection {
+        match self {
+            Self::Insertion | Self::KeyAsc => SortDirection::Ascending,
+            Self::KeyDesc | Self::ValueLenDesc => SortDirection::Descending,
+        }
+    }
+}
+
+impl Display for SortMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Insertion => write!(f, "none"),
+            Self::KeyAsc | Self::KeyDesc => write!(f, "key"),
+            Self::ValueLenDesc => write!(f, "length"),
+        }
+    }
+}
+
+/// the direction the list is sorted in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    /// the natural order of the sort mode.
+    #[default]
+    Ascending,
+    /// the reverse of it.
+    Descending,
+}
+
+impl SortDirection {
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            Self::Ascending => Self::Descending,
+            Self::Descending => Self::Ascending,
+        }
+    }
+
+    pub(crate) fn apply(self, ordering: std::cmp::Ordering) -> std::cmp::Ordering {
+        match self {
+            Self::Ascending => ordering,
+            Self::Descending => ordering.reverse(),
+        }
+    }
+}
+
+impl Display for SortDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ascending => write!(f, "↑"),
+            Self::Descending => write!(f, "↓"),
+        }
+    }
+}
+
+/// the search state.
+pub struct Search {
+    pub(crate) query: String,
+    /// whether keystrokes are captured by the search prompt.
+    pub(crate) editing: bool,
+    pub(crate) target: SearchTarget,
+    pub(crate) mode: SearchMode,
+    /// the last successfully compiled regex.
+    pub(crate) regex: Option<Regex>,
+    /// the compile error of the current query, if any.
+    pub(crate) error: Option<String>,
+    pub(crate) case_sensitive: bool,
+    /// only keys starting with this exactly are shown, on top of the query.
+    pub(crate) prefix: String,
+    /// whether all variables stay shown and `n` jumps between the matches.
+    pub(crate) jump: bool,
+    /// whether the value of a key is masked, and so never matched.
+    pub(crate) masked: Option<KeyFilter>,
+}
+
+/// a test on the key of a variable.
+pub(crate) type KeyFilter = Box<dyn Fn(&str) -> bool>;
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            editing: false,
+            target: SearchTarget::default(),
+            mode: SearchMode::default(),
+            regex: None,
+            error: None,
+            case_sensitive: true,
+            prefix: String::new(),
+            jump: false,
+            masked: None,
+        }
+    }
+}
+
+impl Search {
+    /// A case-sensitive search for `query` in the `target` field of each
+    /// variable.
+    pub fn new(query: &str, target: SearchTarget, mode: SearchMode) -> Self {
+        let mut search = Self {
+            query: query.to_string(),
+            target,
+            mode,
+            ..Self::default()
+        };
+        search.update();
+        search
+    }
+
+    /// The same search, ignoring case.
+    pub fn ignoring_case(mut self) -> Self {
+        if self.case_sensitive {
+            self.toggle_case();
+        }
+        self
+    }
+
+    /// Why the query is not a valid regex, if it is not.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Recompile the regex if the query changed since the last compile.
+    ///
+    /// An invalid pattern keeps the last valid regex and records the error.
+    pub(crate) fn update(&mut self) {
+        if self.mode != SearchMode::Regex {
+            self.error = None;
+            return;
+        }
+        if self
+            .regex
+            .as_ref()
+            .is_some_and(|r| r.as_str() == self.query)
+        {
+            return;
+        }
+        let regex = RegexBuilder::new(&self.query)
+            .case_insensitive(!self.case_sensitive)
+            .build();
+        match regex {
+            Ok(regex) => {
+                self.regex = Some(regex);
+                self.error = None;
+            }
+            Err(err) => {
+                let message = err.to_string();
+                self.error = message.lines().last().map(str::to_string);
+            }
+        }
+    }
+
+    /// Flip case sensitivity, dropping the cached regex so it gets rebuilt.
+    pub(crate) fn toggle_case(&mut self) {
+        self.case_sensitive = !self.case_sensitive;
+        self.regex = None;
+        self.update();
+    }
+
+    /// A short description of the active search options.
+    pub(crate) fn label(&self) -> String {
+        let case = if self.case_sensitive { "Aa" } else { "aa" };
+        let jump = if self.jump { " jump" } else { "" };
+        format!("{} {} {case}{jump}", self.target, self.mode)
+    }
+
+    /// Clear the query, keeping the target and mode.
+    pub(crate) fn clear(&mut self) {
+        self.query.clear();
+        self.editing = false;
+        self.regex = None;
+        self.update();
+    }
+
+    /// Whether `item` has the prefix and matches the query.
+    pub(crate) fn shows(&self, item: &Environment) -> bool {
+        item.key.starts_with(&self.prefix) && (self.jump || self.matches(item).is_some())
+    }
+
+    /// Whether `item` is one of the matches jumped between.
+    pub(crate) fn hits(&self, item: &Environment) -> bool {
+        self.jump && !self.query.is_empty() && self.matches(item).is_some()
+    }
+
+    /// Match the searched field of `item`, returning the matched char indices.
+    pub fn matches(&self, item: &Environment) -> Option<Vec<usize>> {
+        let text = match self.target {
+            SearchTarget::Key => &item.key,
+            SearchTarget::Value => &item.value,
+        };
+        if self.target == SearchTarget::Value
+            && !self.query.is_empty()
+            && self.masked.as_ref().is_some_and(|masked| masked(&item.key))
+        {
+            return None;
+        }
+        match self.mode {
+            SearchMode::Fuzzy => fuzzy_match(&self.query, text, self.case_sensitive),
+            SearchMode::Regex => match &self.regex {
+                Some(regex) => regex_match(regex, text),
+                None => Some(Vec::new()),
+            },
+        }
+    }
+
+    /// The matched char indices in the key of `item`, if searching keys.
+    pub(crate) fn key_matches(&self, item: &Environment) -> Vec<usize> {
+        match self.target {
+            SearchTarget::Key => self.matches(item).unwrap_or_default(),
+            SearchTarget::Value => Vec::new(),
+        }
+    }
+
+    /// The matched char indices in the value of `item`, if searching values.
+    pub(crate) fn value_matches(&self, item: &Environment) -> Vec<usize> {
+        match self.target {
+            SearchTarget::Key => Vec::new(),
+            SearchTarget::Value => self.matches(item).unwrap_or_default(),
+        }
+    }
+}
+
+/// which field of an `Environment` the search looks at.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchTarget {
+    /// the name of the variable.
+    #[default]
+    Key,
+    /// the value of the variable.
+    Value,
+}
+
+impl SearchTarget {
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            Self::Key => Self::Value,
+            Self::Value => Self::Key,
+        }
+    }
+}
+
+impl Display for SearchTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key => write!(f, "key"),
+            Self::Value => write!(f, "value"),
+        }
+    }
+}
+
+/// how the search query is matched.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// the query chars appear in order, not necessarily next to each other.
+    #[default]
+    Fuzzy,
+    /// the query is a regular expression.
+    Regex,
+}
+
+impl SearchMode {
+    pub(crate) fn toggle(self) -> Self {
+        match self {
+            Self::Fuzzy => Self::Regex,
+            Self::Regex => Self::Fuzzy,
+        }
+    }
+}
+
+impl Display for SearchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fuzzy => write!(f, "fuzzy"),
+            Self::Regex => write!(f, "regex"),
+        }
+    }
+}
+
+/// Match `regex` against `text`, returning the char indices covered by the matches.
+pub(crate) fn regex_match(regex: &Regex, text: &str) -> Option<Vec<usize>> {
+    if !regex.is_match(text) {
+        return None;
+    }
+    let ranges: Vec<_> = regex.find_iter(text).map(|m| m.range()).collect();
+    let positions = text
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (byte, _))| ranges.iter().any(|r| r.contains(byte)))
+        .map(|(i, _)| i)
+        .collect();
+    Some(positions)
+}
+
+/// Match `pattern` as a subsequence of `text`, returning the matched char indices.
+///
+/// Of the ways the pattern matches, the highlighted one reads best: an
+/// unbroken run if there is one, otherwise chars at the start of words where
+/// the rest of the pattern still fits after them.
+pub(crate) fn fuzzy_match(pattern: &str, text: &str, case_sensitive: bool) -> Option<Vec<usize>> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let eq = |p: char, i: usize| chars_eq(p, text[i], case_sensitive);
+
+    // the latest index each pattern char can match at with the rest after it.
+    let mut latest = vec![0; pattern.len()];
+    let mut end = text.len();
+    for (j, &p) in pattern.iter().enumerate().rev() {
+        end = (0..end).rev().find(|&i| eq(p, i))?;
+        latest[j] = end;
+    }
+    if pattern.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let runs = (0..=text.len() - pattern.len())
+        .filter(|&start| (0..pattern.len()).all(|j| eq(pattern[j], start + j)));
+    let mut first_run = None;
+    for start in runs {
+        if is_word_start(&text, start) {
+            first_run = Some(start);
+            break;
+        }
+        first_run.get_or_insert(start);
+    }
+    if let Some(start) = first_run {
+        return Some((start..start + pattern.len()).collect());
+    }
+
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut start = 0;
+    for (j, &p) in pattern.iter().enumerate() {
+        let mut candidates = (start..=latest[j]).filter(|&i| eq(p, i));
+        let first = candidates.next()?;
+        let chosen = std::iter::once(first)
+            .chain(candidates)
+            .find(|&i| is_word_start(&text, i))
+            .unwrap_or(first);
+        positions.push(chosen);
+        start = chosen + 1;
+    }
+    Some(positions)
+}
+
+/// Whether the char at `i` starts a word, after a separator or as the
+/// upper case start of a camel case word.
+fn is_word_start(text: &[char], i: usize) -> bool {
+    let Some(&before) = i.checked_sub(1).and_then(|before| text.get(before)) else {
+        return true;
+    };
+    !before.is_alphanumeric() || before.is_lowercase() && text[i].is_uppercase()
+}
+
+pub(crate) fn chars_eq(a: char, b: char, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        a == b
+    } else {
+        a.to_lowercase().eq(b.to_lowercase())
+    }
+}
+
+/// Enviroment struct, containing the key and value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// the name of the variable.
+    pub key: String,
+    /// what the variable is set to.
+    pub value: String,
+    /// how the variable differs from a file or baseline, when comparing.
+    pub status: Option<DiffStatus>,
+    /// whether the key or value was not valid UTF-8 and had chars replaced.
+    pub lossy: bool,
+    /// the values the variable was set to earlier in a file, which `value`
+    /// overrides.
+    pub overridden: Vec<String>,
+    /// whether the variable is passed on to programs, false for lines of a
+    /// file without `export`.
+    pub exported: bool,
+}
+
+impl Environment {
+    /// Create a new struct from key, and string.
+    pub fn new(key: String, value: String) -> Self {
+        Self {
+            key,
+            value,
+            status: None,
+            lossy: false,
+            overridden: Vec::new(),
+            exported: true,
+        }
+    }
+}
+
+/// how a live variable differs from the one in a file or baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffStatus {
+    /// only set in the live environment.
+    Added,
+    /// only set in the file.
+    Removed,
+    /// set in both with different values.
+    Changed {
+        /// the value in the file.
+        file_value: String,
+    },
+    /// set in both to the same value, only kept when comparing to a baseline.
+    Inherited,
+}
+
+impl DiffStatus {
+    pub(crate) fn marker(&self) -> &'static str {
+        match self {
+            Self::Added => "+ ",
+            Self::Removed => "- ",
+            Self::Changed { .. } => "~ ",
+            Self::Inherited => "  ",
+        }
+    }
+
+    pub(crate) fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Self::Added => theme.added,
+            Self::Removed => theme.removed,
+            Self::Changed { .. } => theme.changed,
+            Self::Inherited => theme.muted,
+        }
+    }
+}
+
+/// Compare the `live` variables against the ones from a `file`, keeping
+/// only those that differ.
+pub(crate) fn diff_variables(live: Vec<Environment>, file: Vec<Environment>) -> Vec<Environment> {
+    let mut variables = compare_variables(live, file);
+    variables.retain(|item| item.status != Some(DiffStatus::Inherited));
+    variables
+}
+
+/// Mark the `live` variables by how they differ from the `earlier` ones,
+/// followed by the earlier ones no longer set.
+pub(crate) fn compare_variables(
+    live: Vec<Environment>,
+    earlier: Vec<Environment>,
+) -> Vec<Environment> {
+    let mut earlier: HashMap<String, Environment> = earlier
+        .into_iter()
+        .map(|item| (item.key.clone(), item))
+        .collect();
+    let mut variables = Vec::new();
+
+    for mut item in live {
+        item.status = Some(match earlier.remove(&item.key) {
+            None => DiffStatus::Added,
+            Some(other) if other.value != item.value => DiffStatus::Changed {
+                file_value: other.value,
+            },
+            Some(_) => DiffStatus::Inherited,
+        });
+        variables.push(item);
+    }
+    let mut removed: Vec<Environment> = earlier.into_values().collect();
+    removed.sort_by(|a, b| a.key.cmp(&b.key));
+    for mut item in removed {
+        item.status = Some(DiffStatus::Removed);
+        variables.push(item);
+    }
+    variables
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+impl Environment {
+    /// Whether the value is a list of paths, either because the key is a
+    /// known one or because it splits into several absolute paths.
+    pub(crate) fn is_path_list(&self) -> bool {
+        if PATH_KEYS.contains(&self.key.as_str()) {
+            return true;
+        }
+        let paths: Vec<PathBuf> = env::split_paths(&self.value).collect();
+        paths.len() > 1 && paths.iter().all(|path| path.is_absolute())
+    }
+}
+
+/// Expand the `$NAME` and `${NAME}` references in `value` with `lookup`.
+///
+/// References that `lookup` cannot resolve are left as written and their
+/// names returned alongside the expanded value.
+pub(crate) fn expand_references(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> (String, Vec<String>) {
+    let mut expanded = String::with_capacity(value.len());
+    let mut unresolved: Vec<String> = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, reference) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &rest[start..start + end + 3]),
+                None => ("", "$"),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &rest[start..start + end + 1])
+            }
+        };
+        if !is_reference_name(name) {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        match lookup(name) {
+            Some(resolved) => expanded.push_str(&resolved),
+            None => {
+                expanded.push_str(reference);
+                if !unresolved.iter().any(|other| other == name) {
+                    unresolved.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[start + reference.len()..];
+    }
+    expanded.push_str(rest);
+    (expanded, unresolved)
+}
+
+/// Whether `name` can be referenced as `$name`.
+pub(crate) fn is_reference_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote `value` so a POSIX shell reads it back verbatim.
+///
+/// Values made only of safe characters are left as is, anything else is
+/// wrapped in single quotes with embedded quotes written as `'\''`.
+pub(crate) fn shell_quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+}
+
+/// Write `items` to `path` as re-sourceable `KEY=VALUE` lines.
+pub(crate) fn write_dotenv(items: &[Environment], path: &Path) -> io::Result<Vec<String>> {
+    // the file can be sourced, so a key that is not a name could run a command.
+    let (items, skipped): (Vec<&Environment>, Vec<&Environment>) = items
+        .iter()
+        .partition(|item| Shell::Posix.is_valid_name(&item.key));
+    // plain `KEY=VALUE` lines, unless some are not exported and `export`
+    // tells them apart.
+    let export = if items.iter().all(|item| item.exported) {
+        ""
+    } else {
+        "export "
+    };
+    let contents: String = items
+        .iter()
+        .map(|item| {
+            let export = if item.exported { export } else { "" };
+            format!("{export}{}={}\n", item.key, shell_quote(&item.value))
+        })
+        .collect();
+    fs::write(path, contents)?;
+    Ok(skipped.into_iter().map(|item| item.key.clone()).collect())
+}
+
+/// the shell syntax a variable is exported in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    /// `export KEY=VALUE`, for sh, bash and zsh.
+    #[default]
+    Posix,
+    /// `set -gx KEY VALUE`.
+    Fish,
+    /// `$env:KEY = 'VALUE'`.
+    #[value(name = "powershell")]
+    PowerShell,
+}
+
+impl Shell {
+    /// Whether this shell reads `key` as one variable name. Anything else
+    /// could end the assignment early and run the rest as a command.
+    ///
+    /// POSIX shells take letters, digits and `_`, not starting with a digit,
+    /// and fish takes the same chars anywhere. PowerShell takes any name in
+    /// `${env:...}` except for the braces and backticks it treats specially.
+    pub(crate) fn is_valid_name(self, key: &str) -> bool {
+        let word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        match self {
+            Self::Posix => key.chars().all(word) && key.starts_with(|c: char| !c.is_ascii_digit()),
+            Self::Fish => !key.is_empty() && key.chars().all(word),
+            Self::PowerShell => {
+                !key.is_empty()
+                    && !key
+                        .chars()
+                        .any(|c| c.is_control() || matches!(c, '{' | '}' | '`'))
+            }
+        }
+    }
+
+    /// How the shell is called in messages.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Posix => "POSIX shells",
+            Self::Fish => "fish",
+            Self::PowerShell => "PowerShell",
+        }
+    }
+
+    /// A line setting `item` in this shell, with the value quoted so it is
+    /// taken literally. The key must be [a valid name](Shell::is_valid_name).
+    pub(crate) fn export_line(self, item: &Environment) -> String {
+        let value = self.quote(&item.value);
+        match (self, item.exported) {
+            (Self::Posix, true) => format!("export {}={value}", item.key),
+            (Self::Posix, false) => format!("{}={value}", item.key),
+            (Self::Fish, true) => format!("set -gx {} {value}", item.key),
+            (Self::Fish, false) => format!("set -g {} {value}", item.key),
+            (Self::PowerShell, exported) => {
+                let scope = if exported { "env:" } else { "" };
+                if item
+                    .key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    format!("${scope}{} = {value}", item.key)
+                } else {
+                    format!("${{{scope}{}}} = {value}", item.key)
+                }
+            }
+        }
+    }
+
+    /// A line removing the variable `key` in this shell.
+    pub(crate) fn unset_line(self, key: &str) -> String {
+        match self {
+            Self::Posix => format!("unset {key}"),
+            Self::Fish => format!("set -e {key}"),
+            Self::PowerShell => {
+                format!(
+                    "Remove-Item -LiteralPath {}",
+                    self.quote(&format!("env:{key}"))
+                )
+            }
+        }
+    }
+
+    /// Quote `value` so this shell reads it back verbatim.
+    ///
+    /// POSIX shells get [`shell_quote`]. Fish takes single-quoted text
+    /// literally except for `\` and `'`, which get a backslash, and
+    /// PowerShell takes it literally except for `'`, which is doubled.
+    pub(crate) fn quote(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Self::Posix => shell_quote(value),
+            Self::Fish => {
+                let value = value.replace('\\', r"\\").replace('\'', r"\'");
+                Cow::Owned(format!("'{value}'"))
+            }
+            Self::PowerShell => Cow::Owned(format!("'{}'", value.replace('\'', "''"))),
+        }
+    }
+
+    /// The file extension of scripts for this shell.
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Posix => "sh",
+            Self::Fish => "fish",
+            Self::PowerShell => "ps1",
+        }
+    }
+}
+
+/// Write `items` to `path` as lines `shell` can source.
+///
+/// Nothing is written if a key is not a valid name in `shell`.
+pub(crate) fn write_export(items: &[Environment], shell: Shell, path: &Path) -> io::Result<()> {
+    if let Some(item) = items.iter().find(|item| !shell.is_valid_name(&item.key)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{:?} is not a valid variable name for {}",
+                item.key,
+                shell.name()
+            ),
+        ));
+    }
+    let contents: String = items
+        .iter()
+        .map(|item| shell.export_line(item) + "\n")
+        .collect();
+    fs::write(path, contents)
+}
+
+/// The lines turning the variables in `original` into the ones in `current`
+/// in `shell`: new and changed variables are set, missing ones unset.
+///
+/// Values that were not valid UTF-8 are left alone, the shell already has
+/// the real ones. Keys the shell would not read as names are left out and
+/// returned instead.
+pub(crate) fn eval_lines(
+    original: &[Environment],
+    current: &[Environment],
+    shell: Shell,
+) -> (Vec<String>, Vec<String>) {
+    let before: HashMap<&str, &Environment> = original
+        .iter()
+        .map(|item| (item.key.as_str(), item))
+        .collect();
+    let after: HashSet<&str> = current.iter().map(|item| item.key.as_str()).collect();
+    let set = current.iter().filter(|item| {
+        !item.lossy
+            && before
+                .get(item.key.as_str())
+                .is_none_or(|old| old.value != item.value || old.exported != item.exported)
+    });
+    let unset = original
+        .iter()
+        .filter(|item| !item.lossy && !after.contains(item.key.as_str()));
+    let mut lines = Vec::new();
+    let mut skipped = Vec::new();
+    for (item, set) in set
+        .map(|item| (item, true))
+        .chain(unset.map(|item| (item, false)))
+    {
+        if !shell.is_valid_name(&item.key) {
+            skipped.push(item.key.clone());
+        } else if set {
+            lines.push(shell.export_line(item));
+        } else {
+            lines.push(shell.unset_line(&item.key));
+        }
+    }
+    (lines, skipped)
+}
+
+/// Write `items` to `path` as a JSON object of keys to values.
+pub(crate) fn write_json(items: &[Environment], path: &Path) -> io::Result<()> {
+    fs::write(path, to_json(items)? + "\n")
+}
+
+/// Format `items` as a JSON object of keys to values.
+///
+/// When some variables are not exported, each key maps to an object with
+/// the `value` and whether it is `exported` instead.
+pub(crate) fn to_json<'a>(
+    items: impl IntoIterator<Item = &'a Environment>,
+) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&json_map(items))
+}
+
+fn json_map<'a>(
+    items: impl IntoIterator<Item = &'a Environment>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let items: Vec<&Environment> = items.into_iter().collect();
+    let all_exported = items.iter().all(|item| item.exported);
+    items
+        .into_iter()
+        .map(|item| {
+            let value = if all_exported {
+                item.value.clone().into()
+            } else {
+                serde_json::json!({ "value": item.value, "exported": item.exported })
+            };
+            (item.key.clone(), value)
+        })
+        .collect()
+}
+
+/// the version of the snapshot files written by this build.
+///
+/// Version 1 wraps the variables in an object with the version and the time
+/// taken. Files without a version are JSON exports, read as version 0.
+const SNAPSHOT_VERSION: u64 = 1;
+
+/// Write `items` to `path` as a snapshot taken `taken` seconds after the
+/// Unix epoch.
+pub(crate) fn write_snapshot(items: &[Environment], path: &Path, taken: u64) -> io::Result<()> {
+    let snapshot = serde_json::json!({
+        "version": SNAPSHOT_VERSION,
+        "taken": taken,
+        "variables": json_map(items),
+    });
+    fs::write(path, serde_json::to_string_pretty(&snapshot)? + "\n")
+}
+
+/// Parse a snapshot, or a JSON export as an unversioned one.
+///
+/// Returns the variables and the number of entries that were not strings or
+/// `{ "value": ..., "exported": ... }` objects and were skipped.
+pub(crate) fn parse_snapshot(contents: &str) -> io::Result<(Vec<Environment>, usize)> {
+    let invalid = |error: String| io::Error::new(io::ErrorKind::InvalidData, error);
+    let json: serde_json::Value = serde_json::from_str(contents)?;
+    let serde_json::Value::Object(map) = json else {
+        return Err(invalid("a snapshot must be a JSON object".into()));
+    };
+    let variables = match (map.get("version"), map.get("variables")) {
+        (Some(serde_json::Value::Number(version)), Some(serde_json::Value::Object(variables))) => {
+            match version.as_u64() {
+                Some(version) if version <= SNAPSHOT_VERSION => variables,
+                _ => {
+                    return Err(invalid(format!(
+                        "snapshot version {version} is newer than this build reads"
+                    )));
+                }
+            }
+        }
+        _ => &map,
+    };
+
+    let mut items = Vec::new();
+    let mut skipped = 0;
+    for (key, value) in variables {
+        let (value, exported) = match value {
+            serde_json::Value::String(value) => (value, true),
+            serde_json::Value::Object(object) => match object.get("value") {
+                Some(serde_json::Value::String(value)) => {
+                    let exported = object
+                        .get("exported")
+                        .and_then(|exported| exported.as_bool());
+                    (value, exported.unwrap_or(true))
+                }
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            },
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        items.push(Environment {
+            exported,
+            ..Environment::new(key.clone(), value.clone())
+        });
+    }
+    Ok((items, skipped))
+}
+
+/// `secs` after the Unix epoch as a UTC date and time, like `20261014-093000`.
+pub(crate) fn utc_timestamp(secs: u64) -> String {
+    let (days, time) = ((secs / 86_400) as i64, secs % 86_400);
+    // after the `civil_from_days` algorithm of Howard Hinnant, with eras of
+    // 400 years starting on March 1st.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Parse the contents of a `.env` file.
+///
+/// Blank lines and `#` comments are ignored, an optional `export ` prefix is
+/// accepted, and values may be single- or double-quoted. Returns the parsed
+/// variables and the number of malformed lines that were skipped.
+pub(crate) fn parse_dotenv(contents: &str) -> (Vec<Environment>, usize) {
+    let mut variables = Vec::new();
+    let mut skipped = 0;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_dotenv_line(line) {
+            Some(variable) => variables.push(variable),
+            None => skipped += 1,
+        }
+    }
+    (variables, skipped)
+}
+
+pub(crate) fn parse_dotenv_line(line: &str) -> Option<Environment> {
+    let stripped = line.strip_prefix("export ");
+    let (key, value) = stripped.unwrap_or(line).split_once('=')?;
+    let key = key.trim();
+    if !is_valid_key(key) {
+        return None;
+    }
+    Some(Environment {
+        exported: stripped.is_some(),
+        ..Environment::new(key.to_string(), parse_dotenv_value(value.trim())?)
+    })
+}
+
+/// Whether `key` can be used as a variable name.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains('=') && !key.chars().any(char::is_whitespace)
+}
+
+pub(crate) fn parse_dotenv_value(value: &str) -> Option<String> {
+    if let Some(mut rest) = value.strip_prefix('\'') {
+        let mut unquoted = String::with_capacity(rest.len());
+        loop {
+            let (part, tail) = rest.split_once('\'')?;
+            unquoted.push_str(part);
+            // `'\''` closes the quote, adds a quote and opens it again, as
+            // `shell_quote` writes it.
+            match tail.strip_prefix("\\''") {
+                Some(tail) => {
+                    unquoted.push('\'');
+                    rest = tail;
+                }
+                None => return only_comment(tail).then_some(unquoted),
+            }
+        }
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut unescaped = String::with_capacity(rest.len());
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return only_comment(&rest[i + 1..]).then_some(unescaped),
+                '\\' => match chars.next()?.1 {
+                    'n' => unescaped.push('\n'),
+                    't' => unescaped.push('\t'),
+                    other => unescaped.push(other),
+                },
+                c => unescaped.push(c),
+            }
+        }
+        // the quote is never closed.
+        return None;
+    }
+    let value = match value.find(" #") {
+        Some(i) => value[..i].trim_end(),
+        None => value,
+    };
+    Some(value.to_string())
+}
+
+/// Whether `rest`, following a closing quote, is empty or a comment after
+/// whitespace, like in `KEY="value" # note`.
+fn only_comment(rest: &str) -> bool {
+    let comment = rest.trim_start();
+    comment.is_empty() || (comment.len() < rest.len() && comment.starts_with('#'))
+}
+
+/// The variables of the process environment, with invalid UTF-8 replaced
+/// so no variable is lost.
+pub fn get_variables() -> Vec<Environment> {
+    let envs = env::vars_os();
+    let mut variables: Vec<Environment> = Vec::new();
+
+    for (key, value) in envs {
+        let (key, value) = (key.to_string_lossy(), value.to_string_lossy());
+        let lossy = matches!(key, Cow::Owned(_)) || matches!(value, Cow::Owned(_));
+        let mut variable = Environment::new(key.into_owned(), value.into_owned());
+        variable.lossy = lossy;
+        variables.push(variable);
+    }
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(pairs: &[(&str, &str)]) -> EnvList {
+        let items = pairs
+            .iter()
+            .map(|(key, value)| Environment::new(key.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        EnvList::new(Box::new(items)).unwrap()
+    }
+
+    fn keys(list: &EnvList) -> Vec<&str> {
+        list.shown().map(|item| item.key.as_str()).collect()
+    }
+
+    fn search(query: &str) -> Search {
+        Search::new(query, SearchTarget::Key, SearchMode::Fuzzy)
+    }
+
+    #[test]
+    fn fuzzy_search_matches_a_subsequence() {
+        let mut list = list(&[("HOME", ""), ("HOSTNAME", ""), ("SHELL", ""), ("PATH", "")]);
+        list.filter(&search("HM"));
+        assert_eq!(keys(&list), ["HOME", "HOSTNAME"]);
+        assert_eq!(fuzzy_match("HM", "HOSTNAME", true), Some(vec![0, 6]));
+        assert_eq!(fuzzy_match("MH", "HOME", true), None);
+        assert_eq!(fuzzy_match("", "HOME", true), Some(vec![]));
+    }
+
+    #[test]
+    fn fuzzy_matches_prefer_runs_and_word_starts() {
+        assert_eq!(
+            fuzzy_match("HOME", "THOME_HOME", true),
+            Some(vec![6, 7, 8, 9])
+        );
+        assert_eq!(fuzzy_match("HOME", "THOMEX", true), Some(vec![1, 2, 3, 4]));
+        assert_eq!(fuzzy_match("ph", "PAGER_HOME", false), Some(vec![0, 6]));
+        assert_eq!(fuzzy_match("CH", "CARGO_HOME", true), Some(vec![0, 6]));
+    }
+
+    #[test]
+    fn search_is_case_sensitive_unless_asked_not_to_be() {
+        let mut list = list(&[("PATH", ""), ("path", ""), ("Pager", "")]);
+        list.filter(&search("pa"));
+        assert_eq!(keys(&list), ["path"]);
+        list.filter(&search("pa").ignoring_case());
+        assert_eq!(keys(&list), ["PATH", "path", "Pager"]);
+    }
+
+    #[test]
+    fn regex_search_respects_case_and_reports_errors() {
+        let mut list = list(&[("CARGO_HOME", ""), ("cargo_target", ""), ("HOME", "")]);
+        let regex = Search::new("^cargo", SearchTarget::Key, SearchMode::Regex);
+        list.filter(&regex);
+        assert_eq!(keys(&list), ["cargo_target"]);
+        list.filter(&regex.ignoring_case());
+        assert_eq!(keys(&list), ["CARGO_HOME", "cargo_target"]);
+
+        let invalid = Search::new("(", SearchTarget::Key, SearchMode::Regex);
+        assert!(invalid.error().is_some());
+    }
+
+    #[test]
+    fn prefix_matches_the_start_of_keys_exactly() {
+        let mut list = list(&[("CARGO_HOME", ""), ("cargo_x", ""), ("MY_CARGO_", "")]);
+        let mut search = search("");
+        search.prefix = "CARGO_".into();
+        list.filter(&search);
+        assert_eq!(keys(&list), ["CARGO_HOME"]);
+    }
+
+    #[test]
+    fn value_search_ignores_keys() {
+        let mut list = list(&[("EDITOR", "vim"), ("VISUAL", "nvim"), ("VIM", "")]);
+        list.filter(&Search::new("vim", SearchTarget::Value, SearchMode::Fuzzy));
+        assert_eq!(keys(&list), ["EDITOR", "VISUAL"]);
+    }
+
+    #[test]
+    fn sorting_breaks_ties_by_insertion_order() {
+        let mut list = list(&[("B", "xx"), ("A", "x"), ("C", "yy"), ("D", "zzz")]);
+        list.sort_by(SortMode::ValueLenDesc, SortDirection::Ascending);
+        assert_eq!(keys(&list), ["D", "B", "C", "A"]);
+        list.sort_by(SortMode::ValueLenDesc, SortDirection::Descending);
+        assert_eq!(keys(&list), ["A", "B", "C", "D"]);
+        list.sort_by(SortMode::KeyAsc, SortDirection::Ascending);
+        assert_eq!(keys(&list), ["A", "B", "C", "D"]);
+        list.sort_by(SortMode::KeyDesc, SortDirection::Ascending);
+        assert_eq!(keys(&list), ["D", "C", "B", "A"]);
+        list.sort_by(SortMode::Insertion, SortDirection::Descending);
+        assert_eq!(keys(&list), ["D", "C", "A", "B"]);
+    }
+
+    #[test]
+    fn filtering_keeps_the_sort_order() {
+        let mut list = list(&[
+            ("PATH", ""),
+            ("MANPATH", ""),
+            ("HOME", ""),
+            ("INFOPATH", ""),
+        ]);
+        list.sort_by(SortMode::KeyAsc, SortDirection::Ascending);
+        list.filter(&search("PATH"));
+        assert_eq!(keys(&list), ["INFOPATH", "MANPATH", "PATH"]);
+    }
+
+    #[test]
+    fn exports_quote_values_for_each_shell() {
+        let item = Environment::new("GREETING".into(), r"it's C:\".into());
+        assert_eq!(
+            Shell::Posix.export_line(&item),
+            r"export GREETING='it'\''s C:\'"
+        );
+        assert_eq!(
+            Shell::Fish.export_line(&item),
+            r"set -gx GREETING 'it\'s C:\\'"
+        );
+        assert_eq!(
+            Shell::PowerShell.export_line(&item),
+            r"$env:GREETING = 'it''s C:\'"
+        );
+    }
+
+    #[test]
+    fn exports_keep_variables_without_export_local() {
+        let (items, _) = parse_dotenv("export PATH=/bin\nLOCAL=1\n");
+        assert!(items[0].exported);
+        assert!(!items[1].exported);
+        assert_eq!(Shell::Posix.export_line(&items[1]), "LOCAL=1");
+        assert_eq!(Shell::Fish.export_line(&items[1]), "set -g LOCAL '1'");
+        assert_eq!(Shell::PowerShell.export_line(&items[1]), "$LOCAL = '1'");
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&items).unwrap()).unwrap();
+        assert_eq!(json["LOCAL"]["exported"], false);
+        assert_eq!(json["PATH"]["value"], "/bin");
+        let json: serde_json::Value = serde_json::from_str(&to_json(&items[..1]).unwrap()).unwrap();
+        assert_eq!(json["PATH"], "/bin");
+    }
+
+    #[test]
+    fn snapshots_load_old_and_current_versions() {
+        let items = vec![Environment::new("HOME".into(), "/home/user".into())];
+        let path = env::temp_dir().join(format!("envirust-snapshot-{}.json", std::process::id()));
+        write_snapshot(&items, &path, 0).unwrap();
+        let (loaded, skipped) = SnapshotFile::new(&path).load().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((loaded, skipped), (items, 0));
+
+        let (loaded, skipped) = parse_snapshot(r#"{"EDITOR": "vim", "N": 1}"#).unwrap();
+        assert_eq!(loaded, [Environment::new("EDITOR".into(), "vim".into())]);
+        assert_eq!(skipped, 1);
+        assert!(parse_snapshot(r#"{"version": 99, "variables": {}}"#).is_err());
+    }
+
+    #[test]
+    fn timestamps_are_utc_dates() {
+        assert_eq!(utc_timestamp(0), "19700101-000000");
+        assert_eq!(utc_timestamp(951_782_400), "20000229-000000");
+        assert_eq!(utc_timestamp(1_791_970_245), "20261014-093045");
+    }
+
+    #[test]
+    fn duplicate_keys_keep_the_last_value() {
+        let (items, _) = parse_dotenv("A=1\nB=2\nA=3\nA=4\n");
+        let mut list = EnvList::new(Box::new(items)).unwrap();
+        list.filter(&search(""));
+        let values: Vec<_> = list.shown().map(|item| item.value.as_str()).collect();
+        assert_eq!(keys(&list), ["A", "B"]);
+        assert_eq!(values, ["4", "2"]);
+        assert_eq!(list.items[0].overridden, ["1", "3"]);
+    }
+
+    #[test]
+    fn variables_are_marked_against_a_baseline() {
+        let mut list = list(&[("A", "1"), ("B", "2"), ("C", "3")]);
+        let bookmarks = Bookmarks::default();
+        let baseline = Baseline {
+            origin: "snapshot".into(),
+            items: list.snapshot(),
+        };
+        list.set_baseline(Some(baseline), &search(""), &bookmarks);
+        list.set_value("B", "two".into(), &search(""), &bookmarks);
+        list.set_value("D", "4".into(), &search(""), &bookmarks);
+        list.remove("C", &search(""), &bookmarks);
+
+        let statuses: Vec<_> = list
+            .shown()
+            .map(|item| (item.key.as_str(), &item.status))
+            .collect();
+        let b = DiffStatus::Changed {
+            file_value: "2".into(),
+        };
+        assert_eq!(
+            statuses,
+            [
+                ("A", &Some(DiffStatus::Inherited)),
+                ("B", &Some(b)),
+                ("D", &Some(DiffStatus::Added)),
+                ("C", &Some(DiffStatus::Removed)),
+            ]
+        );
+
+        list.set_baseline(None, &search(""), &bookmarks);
+        assert!(list.shown().all(|item| item.status.is_none()));
+    }
+
+    #[test]
+    fn quoting_follows_the_shell() {
+        let value = r"it's C:\tmp";
+        assert_eq!(Shell::Posix.quote(value), r"'it'\''s C:\tmp'");
+        assert_eq!(Shell::Fish.quote(value), r"'it\'s C:\\tmp'");
+        assert_eq!(Shell::PowerShell.quote(value), r"'it''s C:\tmp'");
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("/usr/bin"), "/usr/bin");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+
+    #[test]
+    fn eval_lines_set_changes_and_unset_removals() {
+        let original = vec![
+            Environment::new("KEEP".into(), "same".into()),
+            Environment::new("EDIT".into(), "old".into()),
+            Environment::new("GONE".into(), "x".into()),
+        ];
+        let current = vec![
+            Environment::new("KEEP".into(), "same".into()),
+            Environment::new("EDIT".into(), "it's new".into()),
+            Environment::new("ADDED".into(), "1".into()),
+        ];
+        assert_eq!(
+            eval_lines(&original, &current, Shell::Posix).0,
+            [r"export EDIT='it'\''s new'", "export ADDED=1", "unset GONE"]
+        );
+        assert_eq!(
+            eval_lines(&original, &current, Shell::Fish).0[2],
+            "set -e GONE"
+        );
+        assert_eq!(
+            eval_lines(&original, &current, Shell::PowerShell).0[2],
+            "Remove-Item -LiteralPath 'env:GONE'"
+        );
+        assert!(eval_lines(&original, &original, Shell::Posix).0.is_empty());
+
+        // a key like this would run `id` when the output is evaluated.
+        let mut unsafe_keys = current.clone();
+        unsafe_keys.push(Environment::new("X;id".into(), "1".into()));
+        let original: Vec<Environment> = original
+            .into_iter()
+            .chain([Environment::new("Y$(id)".into(), "1".into())])
+            .collect();
+        let (lines, skipped) = eval_lines(&original, &unsafe_keys, Shell::Posix);
+        assert!(
+            !lines
+                .iter()
+                .any(|line| line.contains("X;id") || line.contains("Y$(id)")),
+            "{lines:?}"
+        );
+        assert_eq!(skipped, ["X;id", "Y$(id)"]);
+    }
+
+    #[test]
+    fn exports_refuse_keys_the_shell_would_not_read_as_names() {
+        for shell in [Shell::Posix, Shell::Fish, Shell::PowerShell] {
+            assert!(shell.is_valid_name("MY_VAR_2"), "{shell:?}");
+            assert!(!shell.is_valid_name(""), "{shell:?}");
+        }
+        assert!(!Shell::Posix.is_valid_name("X;id"));
+        assert!(!Shell::Posix.is_valid_name("2X"));
+        assert!(Shell::Fish.is_valid_name("2X"));
+        assert!(!Shell::Fish.is_valid_name("X;id"));
+        assert!(Shell::PowerShell.is_valid_name("ProgramFiles(x86)"));
+        assert!(!Shell::PowerShell.is_valid_name("X}; id; ${Y"));
+        assert!(!Shell::PowerShell.is_valid_name("X`"));
+
+        let items = vec![
+            Environment::new("OK".into(), "1".into()),
+            Environment::new("X;id".into(), "2".into()),
+        ];
+        let path = env::temp_dir().join(format!("envirust-export-{}.sh", std::process::id()));
+        let err = write_export(&items, Shell::Posix, &path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn quoted_dotenv_values_can_end_in_a_comment() {
+        let (items, skipped) = parse_dotenv(concat!(
+            "A=\"x\" # note\n",
+            "B='y'   #note\n",
+            "C=\"a \\\"b\\\" # c\"\n",
+            "D='it'\\''s'\n",
+            "E=\"x\"# no space\n",
+            "F=\"x\" trailing\n",
+            "G=\"unclosed\n",
+        ));
+        let values: Vec<(&str, &str)> = items
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str()))
+            .collect();
+        assert_eq!(
+            values,
+            [("A", "x"), ("B", "y"), ("C", r#"a "b" # c"#), ("D", "it's")]
+        );
+        assert_eq!(skipped, 3);
+    }
+
+    #[test]
+    fn dotenv_exports_skip_keys_that_are_not_names() {
+        let items = vec![
+            Environment::new("OK".into(), "1".into()),
+            Environment::new("X;id".into(), "2".into()),
+            Environment::new("Y$(id)".into(), "3".into()),
+        ];
+        let path = env::temp_dir().join(format!("envirust-dotenv-{}.env", std::process::id()));
+        let skipped = write_dotenv(&items, &path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "OK=1\n");
+        assert_eq!(skipped, ["X;id", "Y$(id)"]);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    config,
+    model::{SortDirection, SortMode},
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// the file the UI state is saved to, inside the state directory.
+const STATE_FILE: &str = "state.toml";
+
+/// the version of the state file written by this build.
+const STATE_VERSION: u32 = 1;
+
+/// the preferences kept between sessions.
+///
+/// Fields missing from the file take their defaults, so files written before
+/// a field was added still load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub version: u32,
+    pub sort: SortMode,
+    pub direction: SortDirection,
+    /// whether secrets are masked.
+    pub masking: bool,
+    /// whether long values wrap in the value pane.
+    pub wrap: bool,
+    pub line_numbers: bool,
+    /// whether values are shown next to the keys.
+    pub columns: bool,
+    /// the share of the screen given to the list, in percent.
+    pub split: u16,
+    pub value_pane: bool,
+    /// the built-in theme picked in the UI, used over the configured one.
+    pub theme: Option<String>,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            sort: SortMode::default(),
+            direction: SortDirection::default(),
+            masking: true,
+            wrap: true,
+            line_numbers: false,
+            columns: false,
+            split: 50,
+            value_pane: true,
+            theme: None,
+        }
+    }
+}
+
+impl UiState {
+    /// Read the state saved in the state directory.
+    ///
+    /// A missing, unreadable or corrupt file, or one written by a newer
+    /// version, gives the defaults.
+    pub fn load() -> Self {
+        config::read_state(STATE_FILE)
+            .ok()
+            .flatten()
+            .and_then(|contents| Self::from_toml(&contents))
+            .unwrap_or_default()
+    }
+
+    fn from_toml(contents: &str) -> Option<Self> {
+        toml::from_str(contents)
+            .ok()
+            .filter(|state: &Self| state.version <= STATE_VERSION)
+    }
+
+    /// Write the state to the state directory.
+    pub fn save(&self) -> io::Result<()> {
+        let state = Self {
+            version: STATE_VERSION,
+            ..self.clone()
+        };
+        let contents = toml::to_string(&state).map_err(io::Error::other)?;
+        config::write_state(STATE_FILE, &contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_state_loads_back() {
+        let state = UiState {
+            wrap: false,
+            split: 30,
+            theme: Some("light".to_string()),
+            ..UiState::default()
+        };
+        let contents = toml::to_string(&state).unwrap();
+        assert_eq!(UiState::from_toml(&contents), Some(state));
+        // fields missing from older files take their defaults.
+        let state = UiState::from_toml("version = 1\nwrap = false").unwrap();
+        assert!(!state.wrap && state.masking);
+    }
+
+    #[test]
+    fn newer_or_corrupt_state_falls_back_to_the_defaults() {
+        let newer = format!("version = {}\nwrap = false", STATE_VERSION + 1);
+        assert_eq!(UiState::from_toml(&newer), None);
+        assert_eq!(UiState::from_toml("wrap = \"sometimes\""), None);
+        assert_eq!(UiState::from_toml("wrap ="), None);
+    }
+}
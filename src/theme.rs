@@ -0,0 +1,222 @@
+use crate::config::{self, CONFIG_FILE};
+use ratatui::style::{
+    Color,
+    palette::tailwind::{BLUE, EMERALD, GREEN, RED, SLATE, STONE, YELLOW, ZINC},
+};
+use std::str::FromStr;
+
+/// the colors used to draw the UI.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// pane titles.
+    pub title_fg: Color,
+    pub title_bg: Color,
+    /// pane contents.
+    pub fg: Color,
+    pub bg: Color,
+    /// line numbers and other secondary text.
+    pub muted: Color,
+    /// the background of the selected list item.
+    pub selection_bg: Color,
+    pub scrollbar_track: Color,
+    pub scrollbar_thumb: Color,
+    /// search matches.
+    pub matched: Color,
+    pub added: Color,
+    pub removed: Color,
+    pub changed: Color,
+    pub error: Color,
+    /// keys of pretty-printed JSON values.
+    pub json_key: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            title_fg: SLATE.c100,
+            title_bg: BLUE.c800,
+            fg: SLATE.c200,
+            bg: SLATE.c950,
+            muted: SLATE.c500,
+            selection_bg: SLATE.c800,
+            scrollbar_track: SLATE.c800,
+            scrollbar_thumb: SLATE.c400,
+            matched: YELLOW.c400,
+            added: GREEN.c400,
+            removed: RED.c400,
+            changed: YELLOW.c400,
+            error: Color::Red,
+            json_key: BLUE.c300,
+        }
+    }
+}
+
+impl Theme {
+    /// The names of the built-in themes.
+    pub const NAMES: [&str; 3] = ["default", "emerald", "light"];
+
+    /// The built-in theme called `name`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "emerald" => Some(Self {
+                title_fg: ZINC.c100,
+                title_bg: EMERALD.c800,
+                fg: ZINC.c200,
+                bg: ZINC.c950,
+                muted: ZINC.c500,
+                selection_bg: ZINC.c800,
+                scrollbar_track: ZINC.c800,
+                scrollbar_thumb: EMERALD.c400,
+                json_key: EMERALD.c300,
+                ..Self::default()
+            }),
+            "light" => Some(Self {
+                title_fg: STONE.c50,
+                title_bg: BLUE.c600,
+                fg: STONE.c800,
+                bg: STONE.c100,
+                muted: STONE.c500,
+                selection_bg: STONE.c300,
+                scrollbar_track: STONE.c300,
+                scrollbar_thumb: STONE.c600,
+                matched: BLUE.c700,
+                added: GREEN.c700,
+                removed: RED.c700,
+                changed: YELLOW.c700,
+                error: RED.c700,
+                json_key: BLUE.c800,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The theme used without colors, leaving every color to the terminal.
+    pub fn monochrome() -> Self {
+        Self {
+            title_fg: Color::Reset,
+            title_bg: Color::Reset,
+            fg: Color::Reset,
+            bg: Color::Reset,
+            muted: Color::Reset,
+            selection_bg: Color::Reset,
+            scrollbar_track: Color::Reset,
+            scrollbar_thumb: Color::Reset,
+            matched: Color::Reset,
+            added: Color::Reset,
+            removed: Color::Reset,
+            changed: Color::Reset,
+            error: Color::Reset,
+            json_key: Color::Reset,
+        }
+    }
+
+    /// Load the theme from `config.toml` in the config directory.
+    ///
+    /// The file selects a built-in theme with `theme = "name"` and may
+    /// override single colors in a `[colors]` table. Returns the theme along
+    /// with warnings about settings that could not be used.
+    pub fn load() -> (Self, Vec<String>) {
+        match config::read_config(CONFIG_FILE) {
+            Ok(Some(contents)) => Self::from_toml(&contents),
+            Ok(None) => (Self::default(), Vec::new()),
+            Err(err) => (
+                Self::default(),
+                vec![format!("could not read {CONFIG_FILE}: {err}")],
+            ),
+        }
+    }
+
+    fn from_toml(contents: &str) -> (Self, Vec<String>) {
+        let table: toml::Table = match toml::from_str(contents) {
+            Ok(table) => table,
+            Err(err) => {
+                let error = format!("invalid {CONFIG_FILE}: {}", err.message());
+                return (Self::default(), vec![error]);
+            }
+        };
+        let mut warnings = Vec::new();
+
+        let mut theme = match table.get("theme").map(|name| name.as_str()) {
+            None => Self::default(),
+            Some(Some(name)) => Self::builtin(name).unwrap_or_else(|| {
+                warnings.push(format!(
+                    "unknown theme '{name}', expected one of {}",
+                    Self::NAMES.join(", ")
+                ));
+                Self::default()
+            }),
+            Some(None) => {
+                warnings.push("theme must be a name".to_string());
+                Self::default()
+            }
+        };
+
+        let colors = table.get("colors").and_then(|colors| colors.as_table());
+        for (name, value) in colors.into_iter().flatten() {
+            let Some(slot) = theme.color_mut(name) else {
+                warnings.push(format!("unknown color '{name}'"));
+                continue;
+            };
+            match value.as_str().map(Color::from_str) {
+                Some(Ok(color)) => *slot = color,
+                _ => warnings.push(format!("invalid color for '{name}'")),
+            }
+        }
+        (theme, warnings)
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let color = match name {
+            "title_fg" => &mut self.title_fg,
+            "title_bg" => &mut self.title_bg,
+            "fg" => &mut self.fg,
+            "bg" => &mut self.bg,
+            "muted" => &mut self.muted,
+            "selection_bg" => &mut self.selection_bg,
+            "scrollbar_track" => &mut self.scrollbar_track,
+            "scrollbar_thumb" => &mut self.scrollbar_thumb,
+            "matched" => &mut self.matched,
+            "added" => &mut self.added,
+            "removed" => &mut self.removed,
+            "changed" => &mut self.changed,
+            "error" => &mut self.error,
+            "json_key" => &mut self.json_key,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_themes_and_colors_are_reported() {
+        let (theme, warnings) = Theme::from_toml("theme = \"dark\"");
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(
+            warnings[0].starts_with("unknown theme 'dark'"),
+            "{warnings:?}"
+        );
+        assert_eq!(theme.bg, Theme::default().bg);
+
+        let (theme, warnings) = Theme::from_toml(
+            "theme = \"light\"\n[colors]\nfg = \"#ff0000\"\nshadow = \"red\"\nbg = \"nope\"",
+        );
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings.contains(&"unknown color 'shadow'".to_string()));
+        assert!(warnings.contains(&"invalid color for 'bg'".to_string()));
+        assert_eq!(theme.fg, Color::Rgb(255, 0, 0));
+        assert_eq!(theme.bg, Theme::builtin("light").unwrap().bg);
+
+        let (_, warnings) = Theme::from_toml("theme = 1");
+        assert_eq!(warnings, ["theme must be a name"]);
+        let (_, warnings) = Theme::from_toml("theme =");
+        assert!(
+            warnings[0].starts_with("invalid config.toml"),
+            "{warnings:?}"
+        );
+    }
+}